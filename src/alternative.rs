@@ -20,11 +20,11 @@ impl Serializable for AlternativeDeclaration {
         ctx: &SerializationContext,
     ) -> Result<(), ::std::fmt::Error> {
         indent(f, ctx.indent)?;
-        write!(f, "alt {}\n", self.name)?;
+        writeln!(f, "alt {}", self.name)?;
         for decl in &self.alternatives {
             decl.serialize(f, &ctx.indented().emitting_option())?;
         }
-        write!(f, "\n")?;
+        writeln!(f)?;
         Ok(())
     }
 }
@@ -49,6 +49,10 @@ impl AlternativeDeclaration {
         self.span = span;
     }
 
+    pub fn span(&self) -> Span {
+        return self.span;
+    }
+
     pub fn name(&self) -> &String {
         return &self.name;
     }
@@ -70,6 +74,13 @@ impl AlternativeDeclaration {
     }
 
     pub fn type_check(&self, table: &SymbolTable) -> Result<(), ParserError> {
+        let mut scope = SymbolTable::new();
+        for alternative in &self.alternatives {
+            scope.insert_symbol(alternative.name(), Symbol::Opt(alternative))?;
+        }
+        for alternative in &self.alternatives {
+            alternative.type_check(table)?;
+        }
         Ok(())
     }
 }
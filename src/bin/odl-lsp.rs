@@ -0,0 +1,15 @@
+#![allow(clippy::needless_return)]
+use odl::lsp::*;
+
+// Speaks the language server protocol over stdin and stdout.
+fn main() {
+    let stdin = std::io::stdin();
+    let code = match serve(&mut stdin.lock(), &mut std::io::stdout()) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            1
+        }
+    };
+    std::process::exit(code);
+}
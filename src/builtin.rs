@@ -0,0 +1,303 @@
+use crate::error::*;
+use crate::token::Span;
+use crate::types::*;
+use crate::value::*;
+
+// Functions that can be called from expressions. Every builtin but `count`,
+// `abs` and `len` is variadic, `min(a, b, c)` is the smallest of its
+// arguments. `count` also accepts a list, in which case it is its length.
+// The arguments of `sum`, `any` and `all` may be lists, whose elements are
+// taken as arguments, so `sum([1, 2], 3)` is `6` and `any([])` is false.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    Count,
+    Min,
+    Max,
+    Sum,
+    Abs,
+    Any,
+    All,
+    Len,
+}
+
+pub const BUILTINS: [Builtin; 8] = [
+    Builtin::Count,
+    Builtin::Min,
+    Builtin::Max,
+    Builtin::Sum,
+    Builtin::Abs,
+    Builtin::Any,
+    Builtin::All,
+    Builtin::Len,
+];
+
+impl Builtin {
+    pub fn lookup(name: &str) -> Option<Builtin> {
+        return BUILTINS.iter().find(|x| x.name() == name).copied();
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Builtin::Count => "count",
+            Builtin::Min => "min",
+            Builtin::Max => "max",
+            Builtin::Sum => "sum",
+            Builtin::Abs => "abs",
+            Builtin::Any => "any",
+            Builtin::All => "all",
+            Builtin::Len => "len",
+        };
+    }
+
+    pub fn is_variadic(&self) -> bool {
+        return !matches!(self, Builtin::Count | Builtin::Abs | Builtin::Len);
+    }
+
+    // Variadic builtins take at least this many arguments, the others take
+    // exactly this many.
+    pub fn arity(&self) -> usize {
+        return 1;
+    }
+
+    pub fn check_arity(&self, span: Span, found: usize) -> Result<(), ParserError> {
+        let arity = self.arity();
+        if found == arity || (self.is_variadic() && found > arity) {
+            return Ok(());
+        }
+        let plural = if arity == 1 { "" } else { "s" };
+        let expected = match self.is_variadic() {
+            true => format!("at least {} argument{}", arity, plural),
+            false => format!("{} argument{}", arity, plural),
+        };
+        return Err(ParserError::new_arity_mismatch(
+            span,
+            self.name().to_owned(),
+            expected,
+            found,
+        ));
+    }
+
+    fn mismatch(&self, span: Span, expected: &str, found: &[Type]) -> ParserError {
+        let found: Vec<String> = found.iter().map(|x| x.to_string()).collect();
+        return ParserError::new_type_mismatch(
+            span,
+            format!("{} arguments for `{}`", expected, self.name()),
+            found.join(" and "),
+        );
+    }
+
+    fn spreads_lists(&self) -> bool {
+        return matches!(self, Builtin::Sum | Builtin::Any | Builtin::All);
+    }
+
+    pub fn type_check(&self, span: Span, args: &[Type]) -> Result<Type, ParserError> {
+        self.check_arity(span, args.len())?;
+        if self.spreads_lists() {
+            let mut elements = Vec::new();
+            for arg in args {
+                match arg {
+                    Type::List(Some(element)) => elements.push((**element).clone()),
+                    Type::List(None) => {}
+                    _ => elements.push(arg.clone()),
+                }
+            }
+            return match self {
+                Builtin::Sum if elements.iter().all(|x| x.is_numeric()) => Ok(elements
+                    .iter()
+                    .fold(Type::Integer, |acc, x| acc.numeric_result(x))),
+                Builtin::Sum => Err(self.mismatch(span, "numeric", args)),
+                _ if elements.iter().all(|x| *x == Type::Bool) => Ok(Type::Bool),
+                _ => Err(self.mismatch(span, "bool", args)),
+            };
+        }
+        return match self {
+            Builtin::Count => match args[0] {
+                Type::Selection | Type::List(_) => Ok(Type::Integer),
+                _ => Err(self.mismatch(span, "selection", args)),
+            },
+            Builtin::Len => match args[0] {
+                Type::List(_) => Ok(Type::Integer),
+                _ => Err(self.mismatch(span, "list", args)),
+            },
+            Builtin::Min | Builtin::Max | Builtin::Abs => {
+                if !args.iter().all(|x| x.is_numeric()) {
+                    return Err(self.mismatch(span, "numeric", args));
+                }
+                Ok(args
+                    .iter()
+                    .fold(Type::Integer, |acc, x| acc.numeric_result(x)))
+            }
+            Builtin::Sum | Builtin::Any | Builtin::All => unreachable!(),
+        };
+    }
+
+    pub fn evaluate(&self, span: Span, args: &[Value]) -> Result<Value, ParserError> {
+        self.check_arity(span, args.len())?;
+        let types: Vec<Type> = args.iter().map(|x| x.type_of()).collect();
+        let result = self.type_check(span, &types)?;
+        let overflow = || ParserError::new_arithmetic_overflow(span);
+        let mut elements = Vec::new();
+        for arg in args {
+            match arg {
+                Value::List(list) if self.spreads_lists() => elements.extend(list.iter()),
+                _ => elements.push(arg),
+            }
+        }
+        return match self {
+            Builtin::Count | Builtin::Len => match &args[0] {
+                Value::List(elements) => Ok(Value::Integer(elements.len() as i64)),
+                Value::Selection(count) => Ok(Value::Integer(*count)),
+                _ => unreachable!(),
+            },
+            Builtin::Abs => match &args[0] {
+                Value::Integer(i) => i.checked_abs().map(Value::Integer).ok_or_else(overflow),
+                Value::Float(v) => Ok(Value::Float(v.abs())),
+                _ => unreachable!(),
+            },
+            Builtin::Sum => {
+                let mut total = Value::Integer(0);
+                for element in elements {
+                    total = total.add(element).ok_or_else(overflow)?;
+                }
+                Ok(total)
+            }
+            Builtin::Min | Builtin::Max => {
+                let wanted = match self {
+                    Builtin::Min => std::cmp::Ordering::Less,
+                    _ => std::cmp::Ordering::Greater,
+                };
+                let mut best = &args[0];
+                for arg in &args[1..] {
+                    if arg.compare(best) == Some(wanted) {
+                        best = arg;
+                    }
+                }
+                // Mixed arguments are promoted like arithmetic operands,
+                // so `min(1, 2.5)` is `1.0`.
+                if result == Type::Float {
+                    return Ok(Value::Float(best.as_float().unwrap()));
+                }
+                Ok(best.clone())
+            }
+            Builtin::Any => Ok(Value::Bool(elements.contains(&&Value::Bool(true)))),
+            Builtin::All => Ok(Value::Bool(!elements.contains(&&Value::Bool(false)))),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::*;
+
+    #[test]
+    fn builtin_arity() {
+        let span = Span::new();
+        let min = Builtin::lookup("min").unwrap();
+        let error = min.type_check(span, &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`min` expects at least 1 argument, found 0"
+        );
+        assert!(Builtin::lookup("abs")
+            .unwrap()
+            .type_check(span, &[Type::Integer, Type::Integer])
+            .unwrap_err()
+            .is_arity_mismatch());
+        assert!(Builtin::lookup("missing").is_none());
+    }
+
+    #[test]
+    fn builtin_evaluation() {
+        let span = Span::new();
+        let args = [Value::Integer(3), Value::Float(1.5), Value::Integer(-2)];
+        assert_eq!(
+            Builtin::Min.evaluate(span, &args).unwrap(),
+            Value::Float(-2.0)
+        );
+        assert_eq!(
+            Builtin::Max.evaluate(span, &args[..1]).unwrap(),
+            Value::Integer(3)
+        );
+        assert_eq!(
+            Builtin::Sum.evaluate(span, &args).unwrap(),
+            Value::Float(2.5)
+        );
+        assert_eq!(
+            Builtin::Abs.evaluate(span, &[Value::Integer(-4)]).unwrap(),
+            Value::Integer(4)
+        );
+        let bools = [Value::Bool(true), Value::Bool(false)];
+        assert_eq!(
+            Builtin::Any.evaluate(span, &bools).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            Builtin::All.evaluate(span, &bools).unwrap(),
+            Value::Bool(false)
+        );
+    }
+
+    #[test]
+    fn builtins_over_lists() {
+        let span = Span::new();
+        let ints = Type::List(Some(Box::new(Type::Integer)));
+        let floats = Type::List(Some(Box::new(Type::Float)));
+        let bools = Type::List(Some(Box::new(Type::Bool)));
+        assert_eq!(
+            Builtin::Sum
+                .type_check(span, &[ints.clone(), Type::Float])
+                .unwrap(),
+            Type::Float
+        );
+        assert_eq!(
+            Builtin::Sum.type_check(span, &[ints]).unwrap(),
+            Type::Integer
+        );
+        assert_eq!(
+            Builtin::Sum.type_check(span, &[floats]).unwrap(),
+            Type::Float
+        );
+        assert!(Builtin::Sum
+            .type_check(span, &[bools.clone(), Type::Integer])
+            .unwrap_err()
+            .is_type_mismatch());
+        assert_eq!(Builtin::All.type_check(span, &[bools]).unwrap(), Type::Bool);
+        assert!(Builtin::Any
+            .type_check(span, &[Type::Integer])
+            .unwrap_err()
+            .is_type_mismatch());
+        assert!(Builtin::Min
+            .type_check(span, &[Type::List(Some(Box::new(Type::Integer)))])
+            .is_err());
+
+        let list = Value::List(vec![Value::Integer(1), Value::Integer(2)]);
+        assert_eq!(
+            Builtin::Sum
+                .evaluate(span, &[list, Value::Integer(3)])
+                .unwrap(),
+            Value::Integer(6)
+        );
+        let empty = [Value::List(Vec::new())];
+        assert_eq!(
+            Builtin::Sum.evaluate(span, &empty).unwrap(),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            Builtin::Any.evaluate(span, &empty).unwrap(),
+            Value::Bool(false)
+        );
+        assert_eq!(
+            Builtin::All.evaluate(span, &empty).unwrap(),
+            Value::Bool(true)
+        );
+        let flags = [Value::List(vec![Value::Bool(true), Value::Bool(false)])];
+        assert_eq!(
+            Builtin::Any.evaluate(span, &flags).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            Builtin::All.evaluate(span, &flags).unwrap(),
+            Value::Bool(false)
+        );
+    }
+}
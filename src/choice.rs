@@ -8,6 +8,7 @@ use std::fmt::Formatter;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ChoiseDeclaration {
+    quantity: Option<i64>,
     symbol: String,
     value: Option<Expression>,
     fields: Vec<ChoiseDeclaration>,
@@ -20,16 +21,22 @@ impl Serializable for ChoiseDeclaration {
         f: &mut Formatter,
         ctx: &SerializationContext,
     ) -> Result<(), ::std::fmt::Error> {
+        indent(f, ctx.indent)?;
+        if let Some(quantity) = self.quantity {
+            write!(f, "{} ", quantity)?;
+        }
         write!(f, "{}", self.symbol)?;
         if self.value.as_ref().is_some() {
             write!(f, " = ")?;
             self.value.as_ref().unwrap().serialize(f, ctx)?;
         }
-        write!(f, "\n")?;
+        writeln!(f)?;
         for field in &self.fields {
             field.serialize(f, &ctx.indented())?;
         }
-        write!(f, "\n")?;
+        if ctx.indent == 0 {
+            writeln!(f)?;
+        }
         return Ok(());
     }
 }
@@ -41,10 +48,10 @@ impl Display for ChoiseDeclaration {
 }
 
 impl ChoiseDeclaration {
-    pub fn new(symbol: String, exp: Option<Expression>, span: Span) -> ChoiseDeclaration {
+    pub fn new(symbol: String, value: Option<Expression>, span: Span) -> ChoiseDeclaration {
         let fields = Vec::new();
-        let value = None;
         return ChoiseDeclaration {
+            quantity: None,
             symbol,
             value,
             fields,
@@ -56,6 +63,7 @@ impl ChoiseDeclaration {
         let fields = Vec::new();
         let value = Some(exp);
         return ChoiseDeclaration {
+            quantity: None,
             symbol,
             value,
             fields,
@@ -67,10 +75,28 @@ impl ChoiseDeclaration {
         self.span = span;
     }
 
+    pub fn span(&self) -> Span {
+        return self.span;
+    }
+
     pub fn symbol(&self) -> &String {
         return &self.symbol;
     }
 
+    // How many times the selection is taken, `9 tactical marines` has a
+    // quantity of 9. Selections without a leading count have none.
+    pub fn quantity(&self) -> Option<i64> {
+        return self.quantity;
+    }
+
+    pub fn set_quantity(&mut self, quantity: Option<i64>) {
+        self.quantity = quantity;
+    }
+
+    pub fn get_value(&self) -> Option<&Expression> {
+        return self.value.as_ref();
+    }
+
     pub fn get_fields_mut(&mut self) -> &mut Vec<ChoiseDeclaration> {
         return (self.fields).as_mut();
     }
@@ -87,7 +113,21 @@ impl ChoiseDeclaration {
         return self.get_fields_mut().get_mut(i);
     }
 
+    // Selections are checked against a codex by validation::validate. When
+    // the document declares its codex as well, a selection must at least
+    // name one of its entries, options or alternatives, so that a typo such
+    // as `cnst a = 1` is not taken for a selection.
     pub fn type_check(&self, table: &SymbolTable) -> Result<(), ParserError> {
-        Ok(())
+        if table.is_empty() {
+            return Ok(());
+        }
+        return match table.get(&self.symbol) {
+            Some(Symbol::Entry(_)) | Some(Symbol::Opt(_)) | Some(Symbol::Alt(_)) => Ok(()),
+            _ if table.alternative_option(&self.symbol).is_some() => Ok(()),
+            _ => Err(ParserError::new_unresolved_name(
+                self.span,
+                self.symbol.clone(),
+            )),
+        };
     }
 }
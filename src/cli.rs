@@ -0,0 +1,467 @@
+use crate::declaration::Declaration;
+use crate::diagnostic::*;
+use crate::document::Document;
+use crate::format::*;
+use crate::json::ToJson;
+use crate::parser::Parser;
+use crate::validation::{Origin, ValidationError};
+use std::io::{Read, Write};
+
+pub const SUCCESS: i32 = 0;
+pub const DIAGNOSTICS: i32 = 1;
+pub const USAGE_ERROR: i32 = 2;
+
+pub const USAGE: &str = "usage:
+    odl-check check [--emit json] [<file>...]
+    odl-check fmt [--check] [--indent <width>] [--tabs] [<file>...]
+    odl-check eval <expression> [<file>]
+    odl-check validate --codex <file> <roster>
+
+Files default to the standard input when omitted. `fmt` indents by 4
+spaces unless told otherwise.
+";
+
+// The streams a command talks to, so that commands can be run against
+// in memory buffers.
+pub struct Io<'a> {
+    pub stdin: &'a mut dyn Read,
+    pub stdout: &'a mut dyn Write,
+    pub stderr: &'a mut dyn Write,
+}
+
+// A file or the standard input, with the name used in diagnostics.
+struct Source {
+    name: String,
+    text: String,
+}
+
+impl Source {
+    fn read(path: &str, io: &mut Io) -> Result<Source, String> {
+        let mut text = String::new();
+        if path == "-" {
+            io.stdin
+                .read_to_string(&mut text)
+                .map_err(|x| format!("cannot read the standard input: {}", x))?;
+            return Ok(Source {
+                name: "<stdin>".to_owned(),
+                text,
+            });
+        }
+        text =
+            std::fs::read_to_string(path).map_err(|x| format!("cannot read `{}`: {}", path, x))?;
+        return Ok(Source {
+            name: path.to_owned(),
+            text,
+        });
+    }
+
+    // Parses the source, reporting every syntax error.
+    fn parse(&self, io: &mut Io) -> Option<Document> {
+        let (document, errors) = Parser::parse_with_recovery(&self.text);
+        for error in &errors {
+            let _ = write!(io.stderr, "{}", render_error(&self.text, &self.name, error));
+        }
+        if !errors.is_empty() {
+            return None;
+        }
+        return Some(document);
+    }
+
+    // Parses and type checks the source, reporting every diagnostic.
+    fn document(&self, io: &mut Io) -> Option<Document> {
+        let document = self.parse(io)?;
+        if let Err(error) = document.type_check() {
+            let _ = write!(
+                io.stderr,
+                "{}",
+                render_error(&self.text, &self.name, &error)
+            );
+            return None;
+        }
+        return Some(document);
+    }
+}
+
+fn usage(io: &mut Io, message: &str) -> i32 {
+    let _ = writeln!(io.stderr, "error: {}", message);
+    let _ = write!(io.stderr, "{}", USAGE);
+    return USAGE_ERROR;
+}
+
+fn sources(paths: &[String], io: &mut Io) -> Result<Vec<Source>, i32> {
+    let stdin = ["-".to_owned()];
+    let paths = if paths.is_empty() { &stdin[..] } else { paths };
+    let mut sources = Vec::new();
+    for path in paths {
+        match Source::read(path, io) {
+            Ok(source) => sources.push(source),
+            Err(message) => {
+                let _ = writeln!(io.stderr, "error: {}", message);
+                return Err(DIAGNOSTICS);
+            }
+        }
+    }
+    return Ok(sources);
+}
+
+// Checks the sources. With `--emit json` every valid document is also
+// printed as JSON, one per line.
+fn check(args: &[String], io: &mut Io) -> i32 {
+    let mut emit_json = false;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg != "--emit" {
+            paths.push(arg.clone());
+            continue;
+        }
+        match args.next().map(|x| x.as_str()) {
+            Some("json") => emit_json = true,
+            Some(other) => return usage(io, &format!("unknown format `{}`", other)),
+            None => return usage(io, "missing format after `--emit`"),
+        }
+    }
+
+    let sources = match sources(&paths, io) {
+        Ok(sources) => sources,
+        Err(code) => return code,
+    };
+    let mut code = SUCCESS;
+    for source in &sources {
+        match source.document(io) {
+            Some(document) if emit_json => {
+                let _ = writeln!(io.stdout, "{}", document.to_json());
+            }
+            Some(_) => {}
+            None => code = DIAGNOSTICS,
+        }
+    }
+    return code;
+}
+
+// Prints the formatted sources, or with `--check` only reports the ones
+// that are not formatted.
+fn fmt(args: &[String], io: &mut Io) -> i32 {
+    let mut options = FormatOptions::new();
+    let mut check = false;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--tabs" => options.indent_style = IndentStyle::Tabs,
+            "--indent" => match args.next().map(|x| x.parse::<usize>()) {
+                Some(Ok(width)) if width > 0 => options.indent_width = width,
+                _ => return usage(io, "`--indent` expects a positive width"),
+            },
+            _ => paths.push(arg.clone()),
+        }
+    }
+
+    let sources = match sources(&paths, io) {
+        Ok(sources) => sources,
+        Err(code) => return code,
+    };
+    let mut code = SUCCESS;
+    for source in &sources {
+        let document = match source.parse(io) {
+            Some(document) => document,
+            None => {
+                code = DIAGNOSTICS;
+                continue;
+            }
+        };
+        let formatted = format_document(&source.text, &document, &options);
+        if !check {
+            let _ = write!(io.stdout, "{}", formatted);
+        } else if formatted != source.text {
+            let _ = writeln!(io.stderr, "error: `{}` is not formatted", source.name);
+            code = DIAGNOSTICS;
+        }
+    }
+    return code;
+}
+
+fn eval(args: &[String], io: &mut Io) -> i32 {
+    let (expression, paths) = match args {
+        [expression] => (expression, None),
+        [expression, path] => (expression, Some(path)),
+        [] => return usage(io, "missing expression"),
+        _ => return usage(io, "unexpected arguments after the file"),
+    };
+    let document = match paths {
+        Some(path) => match sources(std::slice::from_ref(path), io) {
+            Ok(sources) => match sources[0].document(io) {
+                Some(document) => document,
+                None => return DIAGNOSTICS,
+            },
+            Err(code) => return code,
+        },
+        None => Document::new(),
+    };
+    return match document.evaluate(expression) {
+        Ok(value) => {
+            let _ = writeln!(io.stdout, "{}", value);
+            SUCCESS
+        }
+        Err(error) => {
+            let _ = write!(
+                io.stderr,
+                "{}",
+                render_error(expression, "<expression>", &error)
+            );
+            DIAGNOSTICS
+        }
+    };
+}
+
+// Errors of validation are rendered against the document they point into.
+fn validation_error(codex: &Source, roster: &Source, error: &ValidationError) -> String {
+    let source = match error.origin {
+        Origin::Codex => codex,
+        Origin::Roster => roster,
+    };
+    return render_error(&source.text, &source.name, &error.error);
+}
+
+// Reports the violations of the roster, or prints its points when there
+// are none.
+fn validate(args: &[String], io: &mut Io) -> i32 {
+    let mut codex = None;
+    let mut roster = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--codex" {
+            match args.next() {
+                Some(path) if codex.is_none() => codex = Some(path.clone()),
+                Some(_) => return usage(io, "`--codex` given more than once"),
+                None => return usage(io, "missing file after `--codex`"),
+            }
+        } else if roster.is_none() {
+            roster = Some(arg.clone());
+        } else {
+            return usage(io, &format!("unexpected argument `{}`", arg));
+        }
+    }
+    let (codex, roster) = match (codex, roster) {
+        (Some(codex), Some(roster)) => (codex, roster),
+        (None, _) => return usage(io, "missing `--codex <file>`"),
+        (_, None) => return usage(io, "missing roster file"),
+    };
+
+    let sources = match sources(&[codex, roster], io) {
+        Ok(sources) => sources,
+        Err(code) => return code,
+    };
+    let (codex, roster) = (&sources[0], &sources[1]);
+    let codex_document = codex.document(io);
+    let roster_document = roster.document(io);
+    let (codex_document, roster_document) = match (codex_document, roster_document) {
+        (Some(codex), Some(roster)) => (codex, roster),
+        _ => return DIAGNOSTICS,
+    };
+
+    let violations = match roster_document.validate_against(&codex_document) {
+        Ok(violations) => violations,
+        Err(error) => {
+            let _ = write!(io.stderr, "{}", validation_error(codex, roster, &error));
+            return DIAGNOSTICS;
+        }
+    };
+    for violation in &violations {
+        let rendered = render(
+            &roster.text,
+            &roster.name,
+            &violation.message,
+            Some(violation.span),
+        );
+        let _ = write!(io.stderr, "{}", rendered);
+    }
+    if !violations.is_empty() {
+        return DIAGNOSTICS;
+    }
+
+    let has_choices = roster_document
+        .entries
+        .iter()
+        .any(|x| matches!(x, Declaration::Choice(_)));
+    if has_choices {
+        match roster_document.points_against(&codex_document) {
+            Ok(points) => {
+                let _ = write!(io.stdout, "{}", points);
+            }
+            Err(error) => {
+                let _ = write!(io.stderr, "{}", validation_error(codex, roster, &error));
+                return DIAGNOSTICS;
+            }
+        }
+    }
+    return SUCCESS;
+}
+
+// Runs the command described by the arguments, without the program name,
+// and returns the exit code: 0 on success, 1 when diagnostics were
+// reported and 2 when the arguments are wrong.
+pub fn run(args: &[String], io: &mut Io) -> i32 {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return usage(io, "missing command"),
+    };
+    return match command {
+        "check" => check(rest, io),
+        "fmt" => fmt(rest, io),
+        "eval" => eval(rest, io),
+        "validate" => validate(rest, io),
+        "help" | "-h" | "--help" => {
+            let _ = write!(io.stdout, "{}", USAGE);
+            SUCCESS
+        }
+        _ => usage(io, &format!("unknown command `{}`", command)),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::*;
+
+    fn run_with(args: &[&str], stdin: &str) -> (i32, String, String) {
+        let args: Vec<String> = args.iter().map(|x| x.to_string()).collect();
+        let mut stdin = stdin.as_bytes();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut io = Io {
+            stdin: &mut stdin,
+            stdout: &mut stdout,
+            stderr: &mut stderr,
+        };
+        let code = run(&args, &mut io);
+        return (
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        );
+    }
+
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("odl-cli-{}-{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        return path.to_str().unwrap().to_owned();
+    }
+
+    #[test]
+    fn usage_errors() {
+        let (code, stdout, stderr) = run_with(&[], "");
+        assert_eq!(code, USAGE_ERROR);
+        assert_eq!(stdout, "");
+        assert!(stderr.starts_with("error: missing command\nusage:"));
+
+        assert_eq!(run_with(&["lint"], "").0, USAGE_ERROR);
+        assert_eq!(run_with(&["eval"], "").0, USAGE_ERROR);
+        assert_eq!(run_with(&["validate", "roster.odl"], "").0, USAGE_ERROR);
+        assert_eq!(run_with(&["--help"], "").0, SUCCESS);
+    }
+
+    #[test]
+    fn check_command() {
+        let (code, stdout, stderr) = run_with(&["check"], "const a = 4\n");
+        assert_eq!((code, stdout.as_str(), stderr.as_str()), (SUCCESS, "", ""));
+
+        let (code, _, stderr) = run_with(&["check"], "const a = 4\nconst = 4\n");
+        assert_eq!(code, DIAGNOSTICS);
+        assert!(stderr.contains(" --> <stdin>:2:7\n"));
+
+        let good = temp_file("good.odl", "const a = 4\n");
+        let bad = temp_file("bad.odl", "const a = b\n");
+        let (code, _, stderr) = run_with(&["check", &good, &bad], "");
+        assert_eq!(code, DIAGNOSTICS);
+        assert!(stderr.contains(&format!("--> {}:1:11", bad)));
+        assert!(!stderr.contains(&good));
+
+        let (code, stdout, _) = run_with(&["check", "--emit", "json"], "const a = 4\n");
+        assert_eq!(code, SUCCESS);
+        assert!(stdout.starts_with("{\"declarations\":[{\"kind\":\"const\",\"name\":\"a\""));
+        assert!(stdout.ends_with("}\n"));
+        assert_eq!(run_with(&["check", "--emit", "xml"], "").0, USAGE_ERROR);
+
+        let (code, _, stderr) = run_with(&["check", "/missing/file.odl"], "");
+        assert_eq!(code, DIAGNOSTICS);
+        assert!(stderr.starts_with("error: cannot read `/missing/file.odl`"));
+    }
+
+    #[test]
+    fn fmt_and_eval_commands() {
+        let (code, stdout, _) = run_with(&["fmt"], "const a = (1+2) # three\n");
+        assert_eq!(code, SUCCESS);
+        assert_eq!(stdout, "const a = 1 + 2 # three\n");
+
+        let (code, _, stderr) = run_with(&["fmt", "--check"], "const a = (1+2)\n");
+        assert_eq!(code, DIAGNOSTICS);
+        assert_eq!(stderr, "error: `<stdin>` is not formatted\n");
+        let formatted = "alt a\n  b\n";
+        let (code, _, _) = run_with(&["fmt", "--check", "--indent", "2"], formatted);
+        assert_eq!(code, SUCCESS);
+        assert_eq!(run_with(&["fmt", "--indent", "x"], "").0, USAGE_ERROR);
+
+        let (code, stdout, _) = run_with(&["eval", "min(3, 2) * 2"], "");
+        assert_eq!((code, stdout.as_str()), (SUCCESS, "4\n"));
+
+        let file = temp_file("eval.odl", "const a = 4\n");
+        let (code, stdout, _) = run_with(&["eval", "a + 1", &file], "");
+        assert_eq!((code, stdout.as_str()), (SUCCESS, "5\n"));
+
+        let (code, stdout, stderr) = run_with(&["eval", "missing + 1"], "");
+        assert_eq!((code, stdout.as_str()), (DIAGNOSTICS, ""));
+        assert!(stderr.starts_with("error: cannot find `missing` in this scope"));
+
+        let (code, stdout, stderr) = run_with(&["eval", "[1, \"a\"]"], "");
+        assert_eq!((code, stdout.as_str()), (DIAGNOSTICS, ""));
+        assert!(stderr.starts_with("error: expected list element of type integer"));
+    }
+
+    #[test]
+    fn validate_command() {
+        let codex = temp_file(
+            "codex.odl",
+            "entry squad\n const cost per model = 10\n 5-10 marines\n",
+        );
+        let roster = temp_file("roster.odl", "squad\n 5 marines\n");
+        let (code, stdout, stderr) = run_with(&["validate", "--codex", &codex, &roster], "");
+        assert_eq!((code, stderr.as_str()), (SUCCESS, ""));
+        assert_eq!(stdout, "total: 50\n squad: 50\n  5 marines: 0\n");
+
+        let roster = temp_file("short.odl", "squad\n 3 marines\n");
+        let (code, stdout, stderr) = run_with(&["validate", &roster, "--codex", &codex], "");
+        assert_eq!((code, stdout.as_str()), (DIAGNOSTICS, ""));
+        assert!(stderr.starts_with("error: expected 5-10 `marines` in `squad`, found 3\n"));
+    }
+
+    #[test]
+    fn validation_errors_point_into_their_document() {
+        // A requirement that fails on this roster is one of its violations.
+        let codex = temp_file(
+            "divided.odl",
+            "entry squad\n 5-10 marines\n\n require\n  10 / (count(marines) - 5) > 0\n  \"odd\"\n",
+        );
+        let roster = temp_file("five.odl", "squad\n 5 marines\n\nsquad\n 4 marines\n");
+        let (code, _, stderr) = run_with(&["validate", "--codex", &codex, &roster], "");
+        assert_eq!(code, DIAGNOSTICS);
+        assert!(stderr.starts_with("error: cannot check \"odd\": division by zero\n"));
+        assert!(stderr.contains(&format!("--> {}:1:1\n", roster)));
+        assert!(stderr.contains("error: expected 5-10 `marines` in `squad`, found 4\n"));
+
+        // A cost that cannot be computed is an error of the codex.
+        let codex = temp_file(
+            "priced.odl",
+            "entry squad\n const cost = 10 / (count(marines) - 5)\n 5-10 marines\n",
+        );
+        let roster = temp_file("priced-five.odl", "squad\n 5 marines\n");
+        let (code, _, stderr) = run_with(&["validate", "--codex", &codex, &roster], "");
+        assert_eq!(code, DIAGNOSTICS);
+        assert!(stderr.starts_with("error: division by zero\n"));
+        assert!(
+            stderr.contains(&format!("--> {}:2:15\n", codex)),
+            "{}",
+            stderr
+        );
+    }
+}
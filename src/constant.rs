@@ -3,6 +3,8 @@ use crate::expression::*;
 use crate::serialization::*;
 use crate::symbol_table::*;
 use crate::token::Span;
+use crate::types::*;
+use crate::value::*;
 use std::fmt::Display;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -23,14 +25,14 @@ impl Serializable for ConstantBody {
                 e.serialize(f, ctx)?;
             }
             ConstantBody::Content(vec) => {
-                write!(f, "\n")?;
+                writeln!(f)?;
 
                 for entry in vec {
                     entry.serialize(f, &ctx.indented())?;
                 }
             }
         };
-        write!(f, "\n")?;
+        writeln!(f)?;
         Ok(())
     }
 }
@@ -57,24 +59,32 @@ impl ConstantDeclaration {
         self.span = span;
     }
 
+    pub fn span(&self) -> Span {
+        return self.span;
+    }
+
     pub fn name(&self) -> &String {
         return &self.name;
     }
 
-    fn has_initializer(&self) -> bool {
+    pub fn has_initializer(&self) -> bool {
         return match self.body {
             ConstantBody::Direct(_) => true,
             ConstantBody::Content(_) => false,
         };
     }
 
-    fn has_children(&self) -> bool {
+    pub fn has_children(&self) -> bool {
         return match self.body {
             ConstantBody::Direct(_) => false,
             ConstantBody::Content(_) => true,
         };
     }
 
+    pub fn body(&self) -> &ConstantBody {
+        return &self.body;
+    }
+
     pub fn get_initializer(&self) -> Option<&Expression> {
         return match &self.body {
             ConstantBody::Direct(ref exp) => Some(exp),
@@ -97,17 +107,62 @@ impl ConstantDeclaration {
     }
 
     pub fn get_field(&self, i: usize) -> Option<&ConstantDeclaration> {
-        return match self.get_fields() {
-            None => None,
-            Some(ref children) => Some(&children[i]),
-        };
+        return self.get_fields().and_then(|x| x.get(i));
     }
 
     pub fn get_field_mut(&mut self, i: usize) -> Option<&mut ConstantDeclaration> {
         return self.get_fields_mut().and_then(|x| x.get_mut(i));
     }
 
+    pub fn infer_type(&self, table: &SymbolTable) -> Result<Type, ParserError> {
+        return match &self.body {
+            ConstantBody::Direct(initializer) => {
+                let mut scope = table.make_child();
+                scope.mark_pending(self);
+                initializer.type_check(&scope)
+            }
+            // A field referring back to the record it belongs to is cyclic
+            // as well.
+            ConstantBody::Content(fields) => {
+                let mut scope = table.make_child();
+                scope.mark_pending(self);
+                for field in fields {
+                    field.infer_type(&scope)?;
+                }
+                Ok(Type::Record(self.name.clone()))
+            }
+        };
+    }
+
+    pub fn evaluate(&self, table: &SymbolTable) -> Result<Value, ParserError> {
+        return match &self.body {
+            ConstantBody::Direct(initializer) => {
+                let mut scope = table.make_child();
+                scope.mark_pending(self);
+                initializer.evaluate(&scope)
+            }
+            ConstantBody::Content(fields) => {
+                let mut scope = table.make_child();
+                scope.mark_pending(self);
+                let mut record = Vec::new();
+                for field in fields {
+                    record.push((field.name().clone(), field.evaluate(&scope)?));
+                }
+                Ok(Value::Record(record))
+            }
+        };
+    }
+
     pub fn type_check(&self, table: &SymbolTable) -> Result<(), ParserError> {
+        if let ConstantBody::Content(fields) = &self.body {
+            // Fields are not visible to each other, a detached table is
+            // only used to find duplicates.
+            let mut scope = SymbolTable::new();
+            for field in fields {
+                scope.insert_symbol(field.name(), Symbol::Const(field))?;
+            }
+        }
+        self.infer_type(table)?;
         Ok(())
     }
 }
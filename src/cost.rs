@@ -0,0 +1,173 @@
+use crate::constant::ConstantDeclaration;
+use crate::error::*;
+use crate::symbol_table::*;
+use crate::token::Span;
+use crate::types::Type;
+use crate::value::*;
+use std::fmt::Display;
+
+// Entries, entry fields and options declare their points with constants:
+//
+// opt jump pack
+//  const cost = 15
+//
+// `cost` is paid once for every selection, `cost per model` is multiplied
+// by the number of models: the quantity of an option or field, or every
+// model of the fields of an entry.
+pub const FLAT_COST: &str = "cost";
+pub const MODEL_COST: &str = "cost per model";
+
+// The cost of a selection and of everything selected inside of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Points {
+    pub name: String,
+    pub quantity: Option<i64>,
+    pub cost: Value,
+    pub total: Value,
+    pub span: Span,
+    pub children: Vec<Points>,
+}
+
+impl Points {
+    pub fn new(name: String, quantity: Option<i64>, span: Span) -> Points {
+        return Points {
+            name,
+            quantity,
+            cost: Value::Integer(0),
+            total: Value::Integer(0),
+            span,
+            children: Vec::new(),
+        };
+    }
+
+    pub fn set_cost(&mut self, cost: Value) -> Result<(), ParserError> {
+        let total = self.total.sub(&self.cost).and_then(|x| x.add(&cost));
+        self.total = total.ok_or_else(|| ParserError::new_arithmetic_overflow(self.span))?;
+        self.cost = cost;
+        return Ok(());
+    }
+
+    pub fn add_child(&mut self, child: Points) -> Result<(), ParserError> {
+        let total = self.total.add(&child.total);
+        self.total = total.ok_or_else(|| ParserError::new_arithmetic_overflow(child.span))?;
+        self.children.push(child);
+        return Ok(());
+    }
+
+    fn write(&self, f: &mut std::fmt::Formatter, depth: usize) -> std::fmt::Result {
+        crate::serialization::indent(f, depth)?;
+        if let Some(quantity) = self.quantity {
+            write!(f, "{} ", quantity)?;
+        }
+        writeln!(f, "{}: {}", self.name, self.total)?;
+        for child in &self.children {
+            child.write(f, depth + 1)?;
+        }
+        return Ok(());
+    }
+}
+
+impl Display for Points {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return self.write(f, 0);
+    }
+}
+
+fn is_cost(constant: &ConstantDeclaration) -> bool {
+    let name = normalize_name(constant.name());
+    return name == FLAT_COST || name == MODEL_COST;
+}
+
+fn cost_mismatch(constant: &ConstantDeclaration, found: &Type) -> ParserError {
+    return ParserError::new_type_mismatch(
+        constant.span(),
+        "numeric cost".to_owned(),
+        found.to_string(),
+    );
+}
+
+fn cost_value(constant: &ConstantDeclaration, scope: &SymbolTable) -> Result<Value, ParserError> {
+    let value = constant.evaluate(scope)?;
+    if !value.is_numeric() {
+        return Err(cost_mismatch(constant, &value.type_of()));
+    }
+    return Ok(value);
+}
+
+// Called while type checking the constants of entries, entry fields and
+// options, so that a codex with a cost that is not a number is rejected
+// before any roster is priced with it.
+pub fn check_cost(constant: &ConstantDeclaration, scope: &SymbolTable) -> Result<(), ParserError> {
+    if !is_cost(constant) {
+        return Ok(());
+    }
+    let found = constant.infer_type(scope)?;
+    if !found.is_numeric() {
+        return Err(cost_mismatch(constant, &found));
+    }
+    return Ok(());
+}
+
+// The points declared by a group of constants for a selection of the given
+// number of models.
+pub fn declared_cost<'c>(
+    constants: impl Iterator<Item = &'c ConstantDeclaration>,
+    models: i64,
+    scope: &SymbolTable,
+) -> Result<Value, ParserError> {
+    let mut cost = Value::Integer(0);
+    for constant in constants {
+        let name = normalize_name(constant.name());
+        let value = if name == FLAT_COST {
+            cost_value(constant, scope)?
+        } else if name == MODEL_COST {
+            cost_value(constant, scope)?
+                .mult(&Value::Integer(models))
+                .ok_or_else(|| ParserError::new_arithmetic_overflow(constant.span()))?
+        } else {
+            continue;
+        };
+        cost = cost
+            .add(&value)
+            .ok_or_else(|| ParserError::new_arithmetic_overflow(constant.span()))?;
+    }
+    return Ok(cost);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cost::*;
+
+    #[test]
+    fn points_totals() {
+        let mut squad = Points::new("tactical squad".to_owned(), Some(1), Span::new());
+        squad.set_cost(Value::Integer(70)).unwrap();
+        let mut marines = Points::new("tactical marines".to_owned(), Some(4), Span::new());
+        marines.set_cost(Value::Integer(56)).unwrap();
+        squad.add_child(marines).unwrap();
+        squad.set_cost(Value::Integer(10)).unwrap();
+        assert_eq!(squad.total, Value::Integer(66));
+        assert_eq!(
+            squad.to_string(),
+            "1 tactical squad: 66\n 4 tactical marines: 56\n"
+        );
+    }
+
+    #[test]
+    fn costs_must_be_numeric() {
+        let check = |source: &str| crate::parser::Parser::parse(source).unwrap().type_check();
+        assert!(
+            check("entry squad\n const cost = 10\n 5 marines\n  cost per model = 2.5\n").is_ok()
+        );
+        assert!(check("const cost = \"free\"\n").is_ok());
+        let sources = [
+            "entry squad\n const cost = \"ten\"\n",
+            "entry squad\n 5 marines\n  cost per model = [1]\n",
+            "opt captain\n const cost = 1 > 0\n",
+        ];
+        for source in sources.iter() {
+            let error = check(source).unwrap_err();
+            assert_eq!(error.get_type_mismatch().unwrap().expected, "numeric cost");
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use crate::document::Document;
+use crate::error::*;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::token::*;
+
+// The parts of the source the parser does not see, besides the indentation
+// the lexer derives from them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    Comment,
+    Newline,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+// A token with the exact text it was lexed from. Trivia on the same line
+// after the token is trailing, everything else before it is leading, so a
+// comment at the end of a line belongs to the last token of that line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub kind: TokenKind,
+    pub text: String,
+    pub span: Span,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+impl Trivia {
+    // The token the lexer gives the parser for this trivia, whitespace
+    // counts one column per character as when it is lexed.
+    fn token(&self) -> Token {
+        let kind = match self.kind {
+            TriviaKind::Whitespace => TokenKind::Whitespace(self.text.chars().count() as i64),
+            TriviaKind::Comment => TokenKind::Comment,
+            TriviaKind::Newline => TokenKind::EndLine,
+        };
+        return Token {
+            kind,
+            span: self.span,
+        };
+    }
+}
+
+impl SyntaxToken {
+    // The tokens of the lexer for this token and its trivia.
+    fn lexed(&self) -> impl Iterator<Item = Token> + '_ {
+        let token = Token {
+            kind: self.kind.clone(),
+            span: self.span,
+        };
+        return self
+            .leading
+            .iter()
+            .map(Trivia::token)
+            .chain(std::iter::once(token))
+            .chain(self.trailing.iter().map(Trivia::token));
+    }
+
+    fn write(&self, out: &mut String) {
+        for trivia in &self.leading {
+            out.push_str(&trivia.text);
+        }
+        out.push_str(&self.text);
+        for trivia in &self.trailing {
+            out.push_str(&trivia.text);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Document,
+    // A logical line, such as `entry tactical squad`, followed by the items
+    // indented below it. Lines inside brackets or parentheses continue the
+    // line they were opened on.
+    Item,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    fn new(kind: SyntaxKind) -> SyntaxNode {
+        return SyntaxNode {
+            kind,
+            children: Vec::new(),
+        };
+    }
+
+    // The tokens of this node and of its descendants, in source order.
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        return tokens;
+    }
+
+    // The tokens of the logical line of an item, without its nested items.
+    pub fn line_tokens(&self) -> Vec<&SyntaxToken> {
+        return self
+            .children
+            .iter()
+            .filter_map(|x| match x {
+                SyntaxElement::Token(token) => Some(token),
+                SyntaxElement::Node(_) => None,
+            })
+            .collect();
+    }
+
+    pub fn items(&self) -> Vec<&SyntaxNode> {
+        return self
+            .children
+            .iter()
+            .filter_map(|x| match x {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect();
+    }
+
+    // From the first to the last token, trivia excluded.
+    pub fn span(&self) -> Option<Span> {
+        let tokens = self.tokens();
+        let first = tokens.first()?;
+        let last = tokens.last()?;
+        return Some(first.span.merge(&last.span));
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for token in self.tokens() {
+            token.write(&mut text);
+        }
+        return text;
+    }
+
+    // The innermost item the span starts in, such as the item of a
+    // declaration or field found by the parser. Their spans can run past the
+    // last token of the item, up to where the next line starts.
+    pub fn item_at(&self, span: Span) -> Option<&SyntaxNode> {
+        for item in self.items() {
+            if item.span().is_some_and(|x| contains(x, span.lo)) {
+                return item.item_at(span).or(Some(item));
+            }
+        }
+        return None;
+    }
+}
+
+fn contains(span: Span, location: SourceLocation) -> bool {
+    let position = |x: SourceLocation| (x.row, x.column);
+    return position(span.lo) <= position(location) && position(location) <= position(span.hi);
+}
+
+// A lossless view of a source: concatenating the text of every token and
+// trivia gives back the source byte for byte. Sources that do not parse
+// still have a tree, invalid characters are kept as tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxTree {
+    pub root: SyntaxNode,
+    // The trivia after the last token.
+    pub end: Vec<Trivia>,
+}
+
+fn trivia_kind(kind: &TokenKind) -> Option<TriviaKind> {
+    return match kind {
+        TokenKind::Whitespace(_) => Some(TriviaKind::Whitespace),
+        TokenKind::Comment => Some(TriviaKind::Comment),
+        TokenKind::EndLine => Some(TriviaKind::Newline),
+        _ => None,
+    };
+}
+
+// The lexer emits a token for every whitespace character, they are joined
+// back into a single trivia.
+fn push_trivia(trivia: &mut Vec<Trivia>, kind: TriviaKind, text: &str, span: Span) {
+    if let Some(last) = trivia.last_mut() {
+        if last.kind == TriviaKind::Whitespace && kind == TriviaKind::Whitespace {
+            last.text.push_str(text);
+            last.span = last.span.merge(&span);
+            return;
+        }
+    }
+    trivia.push(Trivia {
+        kind,
+        text: text.to_owned(),
+        span,
+    });
+}
+
+// The indentation of a token that starts a line, the lexer counts every
+// whitespace character as one column.
+fn indentation(leading: &[Trivia]) -> usize {
+    return match leading.last() {
+        Some(trivia) if trivia.kind == TriviaKind::Whitespace => trivia.text.chars().count(),
+        _ => 0,
+    };
+}
+
+fn tokens(source: &str) -> (Vec<SyntaxToken>, Vec<Trivia>) {
+    let mut lexer = Lexer::with_comments(source);
+    let mut tokens: Vec<SyntaxToken> = Vec::new();
+    let mut pending = Vec::new();
+    let mut trailing = false;
+    while let Some((token, text)) = lexer.next_with_text() {
+        let kind = match trivia_kind(&token.kind) {
+            Some(kind) => kind,
+            None => {
+                tokens.push(SyntaxToken {
+                    kind: token.kind,
+                    text: text.to_owned(),
+                    span: token.span,
+                    leading: std::mem::take(&mut pending),
+                    trailing: Vec::new(),
+                });
+                trailing = true;
+                continue;
+            }
+        };
+        if kind == TriviaKind::Newline {
+            trailing = false;
+        }
+        match tokens.last_mut() {
+            Some(last) if trailing => push_trivia(&mut last.trailing, kind, text, token.span),
+            _ => push_trivia(&mut pending, kind, text, token.span),
+        }
+    }
+    return (tokens, pending);
+}
+
+fn close(stack: &mut Vec<(usize, SyntaxNode)>) {
+    let (_, node) = stack.pop().unwrap();
+    let parent = &mut stack.last_mut().unwrap().1;
+    parent.children.push(SyntaxElement::Node(node));
+}
+
+impl SyntaxTree {
+    pub fn parse(source: &str) -> SyntaxTree {
+        let (tokens, end) = tokens(source);
+        let mut stack = vec![(0, SyntaxNode::new(SyntaxKind::Document))];
+        let mut nesting: usize = 0;
+        for (i, token) in tokens.into_iter().enumerate() {
+            let starts_line = i == 0 || token.leading.iter().any(|x| x.kind == TriviaKind::Newline);
+            if starts_line && nesting == 0 {
+                let indent = indentation(&token.leading);
+                while stack.len() > 1 && stack.last().unwrap().0 >= indent {
+                    close(&mut stack);
+                }
+                stack.push((indent, SyntaxNode::new(SyntaxKind::Item)));
+            }
+            match token.kind {
+                TokenKind::LParen | TokenKind::LBracket => nesting += 1,
+                TokenKind::RParen | TokenKind::RBracket => nesting = nesting.saturating_sub(1),
+                _ => {}
+            }
+            stack
+                .last_mut()
+                .unwrap()
+                .1
+                .children
+                .push(SyntaxElement::Token(token));
+        }
+        while stack.len() > 1 {
+            close(&mut stack);
+        }
+        let (_, root) = stack.pop().unwrap();
+        return SyntaxTree { root, end };
+    }
+
+    // The source the tree was built from.
+    pub fn text(&self) -> String {
+        let mut text = self.root.text();
+        for trivia in &self.end {
+            text.push_str(&trivia.text);
+        }
+        return text;
+    }
+
+    // The tokens the lexer would give for the source of the tree, trivia
+    // included, so edits to the tokens of the tree show in what it parses to.
+    pub fn lexed(&self) -> impl Iterator<Item = Token> + '_ {
+        return self
+            .root
+            .tokens()
+            .into_iter()
+            .flat_map(SyntaxToken::lexed)
+            .chain(self.end.iter().map(Trivia::token));
+    }
+
+    // The declarations of the tree, parsed from its tokens. The spans of the
+    // document are the spans of those tokens, `item_at` finds the item of
+    // any of them.
+    pub fn document(&self) -> Result<Document, ParserError> {
+        return Parser::from_tokens(self.lexed()).document();
+    }
+
+    pub fn item_at(&self, span: Span) -> Option<&SyntaxNode> {
+        return self.root.item_at(span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cst::*;
+    use crate::declaration::Declaration;
+    use crate::value::Value;
+
+    #[test]
+    fn lossless_round_trip() {
+        let sources = [
+            "",
+            "# only a comment",
+            "const a = 4",
+            "\n\n# codex\r\nentry  squad # the squad\n\t5-10 marines\n\n  const  cost = [\n   1,\n  2 ]  \n",
+            "const café = \"é\" # ü\nconst b = $ 99999999999999999999\n\n",
+        ];
+        for source in sources.iter() {
+            let tree = SyntaxTree::parse(source);
+            assert_eq!(tree.text(), *source);
+        }
+    }
+
+    #[test]
+    fn trivia_attachment() {
+        let tree = SyntaxTree::parse("# lead\nconst a = 4 # trail\n# end\n");
+        let tokens = tree.root.tokens();
+        assert_eq!(tokens[0].kind, TokenKind::Const);
+        let leading: Vec<TriviaKind> = tokens[0].leading.iter().map(|x| x.kind).collect();
+        assert_eq!(leading, vec![TriviaKind::Comment, TriviaKind::Newline]);
+        let four = tokens[3];
+        assert_eq!(four.text, "4");
+        assert_eq!(four.trailing[1].text, "# trail");
+        assert_eq!(four.trailing.len(), 2);
+        let end: Vec<&str> = tree.end.iter().map(|x| x.text.as_str()).collect();
+        assert_eq!(end, vec!["\n", "# end", "\n"]);
+    }
+
+    #[test]
+    fn items_follow_indentation() {
+        let source = "entry squad\n 5-10 marines = [\n  a,\n b]\n  profile = 4\n require\n  a\n  \"m\"\nconst b = 1\n";
+        let tree = SyntaxTree::parse(source);
+        let items = tree.root.items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].text(),
+            "entry squad\n 5-10 marines = [\n  a,\n b]\n  profile = 4\n require\n  a\n  \"m\""
+        );
+        let fields = items[0].items();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].line_tokens().len(), 10);
+        assert_eq!(fields[0].items().len(), 1);
+        assert_eq!(fields[1].items().len(), 2);
+        let span = fields[0].span().unwrap();
+        assert_eq!((span.lo.row, span.hi.row), (1, 4));
+        assert_eq!(items[1].text(), "\nconst b = 1");
+    }
+
+    #[test]
+    fn document_matches_the_parser() {
+        let source = "const a = 4 # four\n\nentry squad\n 5-10 marines\n";
+        let tree = SyntaxTree::parse(source);
+        assert_eq!(
+            tree.document().unwrap().entries,
+            Parser::parse(source).unwrap().entries
+        );
+        let invalid = "entry squad\n  5-10 marines\n 1 scout\n# end";
+        assert_eq!(
+            SyntaxTree::parse(invalid)
+                .document()
+                .unwrap_err()
+                .to_string(),
+            Parser::parse(invalid).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn document_is_built_from_the_tokens() {
+        let mut tree = SyntaxTree::parse("const a = 4 # four\n");
+        let SyntaxElement::Node(item) = &mut tree.root.children[0] else {
+            panic!("expected an item");
+        };
+        let SyntaxElement::Token(four) = &mut item.children[3] else {
+            panic!("expected a token");
+        };
+        four.kind = TokenKind::Integer(5);
+        four.text = "5".to_owned();
+        assert_eq!(tree.text(), "const a = 5 # four\n");
+        let document = tree.document().unwrap();
+        assert_eq!(document.evaluate("a").unwrap(), Value::Integer(5));
+    }
+
+    #[test]
+    fn items_of_document_spans() {
+        let source = "const a = 4\n\nentry squad\n 5-10 marines\n  profile = 4\n const cost = 1\n";
+        let tree = SyntaxTree::parse(source);
+        let document = tree.document().unwrap();
+        let items = tree.root.items();
+        let constant = tree.item_at(document.entries[0].span()).unwrap();
+        assert_eq!(constant.text(), "const a = 4");
+        let Declaration::Entry(entry) = &document.entries[1] else {
+            panic!("expected an entry");
+        };
+        assert_eq!(tree.item_at(entry.span()), Some(items[1]));
+        let field = tree.item_at(entry.get_fields()[0].span()).unwrap();
+        assert_eq!(field, items[1].items()[0]);
+        assert_eq!(field.text(), "\n 5-10 marines\n  profile = 4");
+        let profile = &entry.get_fields()[0].get_fields()[0];
+        assert_eq!(tree.item_at(profile.span()), Some(field.items()[0]));
+        let cost = tree.item_at(entry.get_constants()[0].span()).unwrap();
+        assert_eq!(cost, items[1].items()[1]);
+    }
+}
@@ -1,11 +1,13 @@
 use crate::alternative::*;
 use crate::choice::*;
 use crate::constant::*;
+use crate::entry::*;
 use crate::error::*;
 use crate::option::*;
+use crate::requirement::*;
 use crate::serialization::*;
 use crate::symbol_table::*;
-use crate::symbol_table::*;
+use crate::token::Span;
 use std::fmt::Display;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -14,6 +16,8 @@ pub enum Declaration {
     Const(ConstantDeclaration),
     Alt(AlternativeDeclaration),
     Choice(ChoiseDeclaration),
+    Entry(EntryDeclaration),
+    Require(RequirementDeclaration),
 }
 
 impl Serializable for Declaration {
@@ -27,6 +31,8 @@ impl Serializable for Declaration {
             Declaration::Opt(dec) => dec.serialize(f, ctx),
             Declaration::Const(dec) => dec.serialize(f, ctx),
             Declaration::Alt(dec) => dec.serialize(f, ctx),
+            Declaration::Entry(dec) => dec.serialize(f, ctx),
+            Declaration::Require(dec) => dec.serialize(f, ctx),
         };
     }
 }
@@ -40,26 +46,42 @@ impl Display for Declaration {
 impl Declaration {
     pub fn name(&self) -> String {
         return match self {
-            Declaration::Choice(_) => "".to_owned(),
+            Declaration::Choice(dec) => dec.symbol().to_owned(),
             Declaration::Opt(dec) => dec.name().to_owned(),
             Declaration::Const(dec) => dec.name().to_owned(),
             Declaration::Alt(dec) => dec.name().to_owned(),
+            Declaration::Entry(dec) => dec.name().to_owned(),
+            Declaration::Require(_) => "".to_owned(),
         };
     }
 
-    pub fn is_choise(&self) -> bool {
+    pub fn span(&self) -> Span {
         return match self {
-            Declaration::Choice(_) => true,
-            _ => false,
+            Declaration::Choice(dec) => dec.span(),
+            Declaration::Opt(dec) => dec.span(),
+            Declaration::Const(dec) => dec.span(),
+            Declaration::Alt(dec) => dec.span(),
+            Declaration::Entry(dec) => dec.span(),
+            Declaration::Require(dec) => dec.span(),
         };
     }
 
+    pub fn is_choise(&self) -> bool {
+        return matches!(self, Declaration::Choice(_));
+    }
+
+    pub fn is_requirement(&self) -> bool {
+        return matches!(self, Declaration::Require(_));
+    }
+
     pub fn type_check(&self, table: &SymbolTable) -> Result<(), ParserError> {
         return match self {
             Declaration::Choice(dec) => dec.type_check(table),
             Declaration::Opt(dec) => dec.type_check(table),
             Declaration::Const(dec) => dec.type_check(table),
             Declaration::Alt(dec) => dec.type_check(table),
+            Declaration::Entry(dec) => dec.type_check(table),
+            Declaration::Require(dec) => dec.type_check(table),
         };
     }
 }
@@ -0,0 +1,107 @@
+use crate::error::ParserError;
+use crate::token::*;
+
+const TAB_WIDTH: usize = 4;
+
+fn display_width(text: &str) -> usize {
+    return text
+        .chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum();
+}
+
+pub fn end_of_source(source: &str) -> Span {
+    let row = source.lines().count().max(1) - 1;
+    let column = source.lines().nth(row).map_or(0, |x| x.chars().count());
+    let location = SourceLocation { row, column };
+    return Span {
+        lo: location,
+        hi: location,
+    };
+}
+
+// Renders a diagnostic in the usual compiler layout:
+//
+// error: expected identifier, found `=`
+//  --> codex.odl:1:7
+//   |
+// 1 | const = 4
+//   |       ^
+pub fn render(source: &str, file: &str, message: &str, span: Option<Span>) -> String {
+    let span = span.unwrap_or_else(|| end_of_source(source));
+    let line = source.lines().nth(span.lo.row).unwrap_or("");
+    let row_label = (span.lo.row + 1).to_string();
+    let gutter = " ".repeat(row_label.len());
+
+    let prefix: String = line.chars().take(span.lo.column).collect();
+    let underlined: String = if span.hi.row == span.lo.row {
+        line.chars()
+            .skip(span.lo.column)
+            .take(span.hi.column.saturating_sub(span.lo.column))
+            .collect()
+    } else {
+        line.chars().skip(span.lo.column).collect()
+    };
+
+    let mut rendered = String::new();
+    rendered.push_str(&format!("error: {}\n", message));
+    rendered.push_str(&format!(
+        "{}--> {}:{}:{}\n",
+        gutter,
+        file,
+        span.lo.row + 1,
+        span.lo.column + 1
+    ));
+    rendered.push_str(&format!("{} |\n", gutter));
+    rendered.push_str(&format!(
+        "{} | {}\n",
+        row_label,
+        line.replace('\t', &" ".repeat(TAB_WIDTH))
+    ));
+    rendered.push_str(&format!(
+        "{} | {}{}\n",
+        gutter,
+        " ".repeat(display_width(&prefix)),
+        "^".repeat(display_width(&underlined).max(1))
+    ));
+    return rendered;
+}
+
+pub fn render_error(source: &str, file: &str, error: &ParserError) -> String {
+    return render(source, file, &error.to_string(), error.span());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn unexpected_token_diagnostic() {
+        let source = "const a = 4\nconst = 4\n";
+        let error = Parser::parse(source).unwrap_err();
+        assert_eq!(
+            render_error(source, "codex.odl", &error),
+            "error: expected identifier, found `=`\n --> codex.odl:2:7\n  |\n2 | const = 4\n  |       ^\n"
+        );
+    }
+
+    #[test]
+    fn end_of_file_diagnostic() {
+        let source = "const a =";
+        let error = Parser::parse(source).unwrap_err();
+        assert_eq!(
+            render_error(source, "codex.odl", &error),
+            "error: expected expression, found end of file\n --> codex.odl:1:10\n  |\n1 | const a =\n  |          ^\n"
+        );
+    }
+
+    #[test]
+    fn underline_spans_the_token() {
+        let source = "const a =\t99999999999999999999";
+        let error = Parser::parse(source).unwrap_err();
+        let rendered = render_error(source, "x", &error);
+        assert!(rendered.contains("1 | const a =    99999999999999999999\n"));
+        assert!(rendered.ends_with("  |              ^^^^^^^^^^^^^^^^^^^^\n"));
+    }
+}
@@ -1,7 +1,11 @@
+use crate::cost::Points;
 use crate::declaration::*;
 use crate::error::*;
+use crate::parser::Parser;
 use crate::serialization::*;
 use crate::symbol_table::*;
+use crate::validation::*;
+use crate::value::*;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
@@ -29,6 +33,12 @@ impl Display for Document {
     }
 }
 
+impl Default for Document {
+    fn default() -> Self {
+        return Document::new();
+    }
+}
+
 impl Document {
     pub fn new() -> Document {
         return Document {
@@ -37,16 +47,380 @@ impl Document {
     }
 
     pub fn type_check(&self) -> Result<(), ParserError> {
+        self.type_check_with_warnings()?;
+        Ok(())
+    }
+
+    // Type checks and evaluates an expression, such as `marine profile`,
+    // against the declarations of this document.
+    pub fn evaluate(&self, expression: &str) -> Result<Value, ParserError> {
+        let expression = Parser::parse_expression(expression)?;
+        let mut table = SymbolTable::new();
+        for decl in &self.entries {
+            table.insert(decl)?;
+        }
+        expression.type_check(&table)?;
+        return expression.evaluate(&table);
+    }
+
+    // Checks the roster selections of this document against the codex
+    // declarations of the same document.
+    pub fn validate(&self) -> Result<Vec<Violation>, ValidationError> {
+        return validate(&self.entries, &self.entries);
+    }
+
+    // Checks the roster selections of this document against the codex
+    // declarations of another one.
+    pub fn validate_against(&self, codex: &Document) -> Result<Vec<Violation>, ValidationError> {
+        return validate(&codex.entries, &self.entries);
+    }
+
+    // The points of the roster selections of this document, priced with
+    // the codex declarations of the same document.
+    pub fn points(&self) -> Result<Points, ValidationError> {
+        return points(&self.entries, &self.entries);
+    }
+
+    pub fn points_against(&self, codex: &Document) -> Result<Points, ValidationError> {
+        return points(&codex.entries, &self.entries);
+    }
+
+    // Like type_check, but also returns the non fatal diagnostics such as
+    // declarations shadowing an outer one.
+    pub fn type_check_with_warnings(&self) -> Result<Vec<ParserError>, ParserError> {
         let mut table = SymbolTable::new();
         for decl in &self.entries {
-            if !decl.is_choise() {
-                table.insert(&decl);
-            }
+            table.insert(decl)?;
         }
 
         for decl in &self.entries {
             decl.type_check(&table)?;
         }
-        Ok(())
+        return Ok(table.take_warnings());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::declaration::Declaration;
+    use crate::option::OptionField;
+    use crate::parser::Parser;
+    use crate::symbol_table::*;
+    use crate::value::Value;
+
+    fn check(source: &str) -> Result<(), crate::error::ParserError> {
+        return Parser::parse(source).unwrap().type_check();
+    }
+
+    #[test]
+    fn well_typed_document() {
+        let source = "const a = 3\nconst b = a * 2.5 + 1\nconst profile\n WS = 4\n AB = a\n\nopt weapon\n const cost = 5\n require\n  cost > 2\n  \"cheap\"\n\nentry squad\n 1-5 marines = a\n require\n  marines == 3 and b >= 1\n  \"m\"\n";
+        assert!(check(source).is_ok());
+    }
+
+    #[test]
+    fn unresolved_name() {
+        let error = check("const a = b + 1\n").unwrap_err();
+        assert_eq!(error.get_unresolved_name().unwrap().name, "b");
+        assert_eq!(error.span().unwrap().lo.column, 10);
+    }
+
+    #[test]
+    fn operand_mismatch() {
+        let error = check("const a = \"a\" + 3\n").unwrap_err();
+        let mismatch = error.get_type_mismatch().unwrap();
+        assert_eq!(mismatch.found, "string and integer");
+        assert!(check("const a = 1 and 2\n").unwrap_err().is_type_mismatch());
+        assert!(check("const a = \"x\" == 2\n")
+            .unwrap_err()
+            .is_type_mismatch());
+        assert!(check("const a = 1 == 2.0\n").is_ok());
+    }
+
+    #[test]
+    fn cyclic_constants() {
+        let error = check("const a = b\nconst b = a + 1\n").unwrap_err();
+        assert!(error.is_cyclic_definition());
+    }
+
+    #[test]
+    fn cyclic_records() {
+        let sources = [
+            "const p\n WS = p\n",
+            "const p\n WS = p.WS\n",
+            "const p\n WS = q\nconst q = p.WS\n",
+        ];
+        for source in sources.iter() {
+            assert!(check(source).unwrap_err().is_cyclic_definition());
+        }
+    }
+
+    #[test]
+    fn members_resolve_where_they_are_declared() {
+        let source = "const a = 1\nconst p\n WS = a\nopt weapon\n const a = \"x\"\n require\n  p.WS == 1\n  \"WS\"\n";
+        let document = Parser::parse(source).unwrap();
+        document.type_check().unwrap();
+
+        // Evaluated from inside `weapon`, where `a` is the string.
+        let mut table = SymbolTable::new();
+        for decl in &document.entries {
+            table.insert(decl).unwrap();
+        }
+        let mut scope = table.make_child();
+        match &document.entries[2] {
+            Declaration::Opt(opt) => match &opt.get_fields()[0] {
+                OptionField::Const(a) => scope.insert_symbol(a.name(), Symbol::Const(a)).unwrap(),
+                _ => panic!("expected a constant"),
+            },
+            _ => panic!("expected an option"),
+        }
+        let member = Parser::parse_expression("p.WS").unwrap();
+        assert_eq!(member.evaluate(&scope).unwrap(), Value::Integer(1));
+        assert_eq!(document.evaluate("p.WS").unwrap(), Value::Integer(1));
+    }
+
+    // The example the syntax was designed from must keep working.
+    #[test]
+    fn examples_fixture() {
+        let document = Parser::parse(include_str!("../examples.txt")).unwrap();
+        document.type_check().unwrap();
+        assert_eq!(document.validate().unwrap(), vec![]);
+        let points = document.points().unwrap();
+        assert_eq!(points.children[0].name, "tactical squad");
+    }
+
+    #[test]
+    fn selections_name_codex_declarations() {
+        let error = check("const b = 2\ncnst a = 1\n").unwrap_err();
+        assert_eq!(error.get_unresolved_name().unwrap().name, "cnst a");
+        assert!(check(
+            "alt weapon\n bolter\nentry squad\n 5-10 marines\n\n1 squad\n 5 marines\nbolter\n"
+        )
+        .is_ok());
+        // A roster alone has nothing to be checked against.
+        assert!(check("cnst a = 1\n").is_ok());
+    }
+
+    #[test]
+    fn requirement_must_be_bool() {
+        assert!(check("require\n 1 + 1\n \"x\"\n")
+            .unwrap_err()
+            .is_type_mismatch());
+    }
+
+    #[test]
+    fn duplicate_declarations() {
+        let error = check("const marine profile = 1\nconst b = 2\nconst marine  profile = 3\n")
+            .unwrap_err();
+        let duplicate = error.get_duplicate_declaration().unwrap();
+        assert_eq!(duplicate.name, "marine profile");
+        assert_eq!(duplicate.previous.lo.row, 0);
+        assert_eq!(duplicate.span.lo.row, 2);
+
+        assert!(check("entry squad\n 1 marines\n 2 marines\n")
+            .unwrap_err()
+            .is_duplicate_declaration());
+        assert!(check("const profile\n WS = 1\n WS = 2\n")
+            .unwrap_err()
+            .is_duplicate_declaration());
+        assert!(check("alt weapon\n bolter\n bolter\n")
+            .unwrap_err()
+            .is_duplicate_declaration());
+    }
+
+    #[test]
+    fn shadowing_warnings() {
+        let document = Parser::parse("const cost = 1\nopt weapon\n const cost = 2\n").unwrap();
+        let warnings = document.type_check_with_warnings().unwrap();
+        assert_eq!(warnings.len(), 1);
+        let shadowed = warnings[0].get_shadowed_declaration().unwrap();
+        assert_eq!(shadowed.name, "cost");
+        assert_eq!(shadowed.previous.lo.row, 0);
+        assert_eq!(shadowed.span.lo.row, 2);
+    }
+
+    #[test]
+    fn evaluate_constants() {
+        let source = "const a = 3\nconst b = a * 2.5 + 1\nconst marine profile\n WS = 4\n AB = a\n\nconst name = \"marine\"\n";
+        let document = Parser::parse(source).unwrap();
+        assert_eq!(document.evaluate("a + 1").unwrap(), Value::Integer(4));
+        assert_eq!(document.evaluate("b").unwrap(), Value::Float(8.5));
+        assert_eq!(document.evaluate("a != 3").unwrap(), Value::Bool(false));
+        assert_eq!(
+            document.evaluate("name == \"marine\" and -a < 0").unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            document.evaluate("marine profile").unwrap(),
+            Value::Record(vec![
+                ("WS".to_owned(), Value::Integer(4)),
+                ("AB".to_owned(), Value::Integer(3))
+            ])
+        );
+    }
+
+    #[test]
+    fn member_access() {
+        let source = "const marine profile\n WS = 4\n stats\n  AB = 2\n\nconst bonus = marine profile.stats.AB + 1\n\nentry squad\n 1-5 marines\n  profile = marine profile\n require\n  marines.profile.WS >= 4\n  \"skilled\"\n";
+        let document = Parser::parse(source).unwrap();
+        assert!(document.type_check().is_ok());
+        assert_eq!(document.evaluate("bonus").unwrap(), Value::Integer(3));
+        assert_eq!(
+            document.evaluate("marine profile.WS").unwrap(),
+            Value::Integer(4)
+        );
+
+        let error = check("const p\n WS = 4\n\nconst a = p.BS\n").unwrap_err();
+        assert_eq!(error.get_unknown_member().unwrap().name, "BS");
+        assert!(check("const a = 1\nconst b = a.x\n")
+            .unwrap_err()
+            .is_unknown_member());
+        assert!(check("const a = b.x\nconst b = a\n")
+            .unwrap_err()
+            .is_cyclic_definition());
+    }
+
+    #[test]
+    fn builtin_calls() {
+        let source = "const a = max(1, 2.5) + abs(-3)\n\nentry squad\n 5-10 marines\n 0-1 sergent\n require\n  count(marines) + count(sergent) <= 10\n  \"too many\"\n";
+        let document = Parser::parse(source).unwrap();
+        assert!(document.type_check().is_ok());
+        assert_eq!(document.evaluate("a").unwrap(), Value::Float(5.5));
+        assert_eq!(
+            document
+                .evaluate("all(a > 5, any(a < 0, sum(1, 2) == 3))")
+                .unwrap(),
+            Value::Bool(true)
+        );
+
+        let error = check("const a = abs(1, 2)\n").unwrap_err();
+        let arity = error.get_arity_mismatch().unwrap();
+        assert_eq!(arity.name, "abs");
+        assert_eq!(arity.found, 2);
+        assert!(check("const a = count(1)\n")
+            .unwrap_err()
+            .is_type_mismatch());
+        assert!(check("const a = any(true)\n")
+            .unwrap_err()
+            .is_unresolved_name());
+        assert_eq!(
+            check("const a = missing(1)\n")
+                .unwrap_err()
+                .get_unresolved_name()
+                .unwrap()
+                .name,
+            "missing"
+        );
+    }
+
+    #[test]
+    fn unary_operands() {
+        let document = Parser::parse("const a = 3\nconst b = not a > 2\n").unwrap();
+        assert!(document.type_check().is_ok());
+        assert_eq!(document.evaluate("b").unwrap(), Value::Bool(false));
+        assert_eq!(
+            document.evaluate("!b and -a < 0").unwrap(),
+            Value::Bool(true)
+        );
+
+        let error = check("const a = not 3\n").unwrap_err();
+        assert_eq!(
+            error.get_type_mismatch().unwrap().expected,
+            "bool operand for `not`"
+        );
+        assert!(check("const a = -(1 < 2)\n")
+            .unwrap_err()
+            .is_type_mismatch());
+    }
+
+    #[test]
+    fn evaluation_order() {
+        let document = crate::document::Document::new();
+        let cases = [
+            ("10 - 3 - 2", Value::Integer(5)),
+            ("100 / 10 / 5", Value::Integer(2)),
+            ("2 * 3 + 4 * 5", Value::Integer(26)),
+            ("1 + 8 / 2 / 2 - 1", Value::Integer(2)),
+            ("20 - 2 * 3 - 4", Value::Integer(10)),
+        ];
+        for (source, expected) in cases.iter() {
+            assert_eq!(document.evaluate(source).unwrap(), *expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn lists() {
+        let source = "const weights = [1, 2.5]\nconst names = [\n \"bolter\"\n \"lascannon\"\n]\n\nentry squad\n 0-1 heavy weapons = []\n require\n  len(heavy weapons) <= 1 and not 3 in weights\n  \"one heavy weapon\"\n";
+        let document = Parser::parse(source).unwrap();
+        assert!(document.type_check().is_ok());
+        assert_eq!(
+            document.evaluate("weights").unwrap(),
+            Value::List(vec![Value::Integer(1), Value::Float(2.5)])
+        );
+        assert_eq!(
+            document.evaluate("\"bolter\" in names").unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            document.evaluate("len(names) + count(weights)").unwrap(),
+            Value::Integer(4)
+        );
+        assert_eq!(
+            document
+                .evaluate("names == [\"bolter\", \"lascannon\"]")
+                .unwrap(),
+            Value::Bool(true)
+        );
+
+        let error = check("const a = [1, \"a\"]\n").unwrap_err();
+        assert_eq!(error.get_type_mismatch().unwrap().found, "string");
+        assert!(check("const a = 1 in 2\n").unwrap_err().is_type_mismatch());
+        assert!(check("const a = \"a\" in [1]\n")
+            .unwrap_err()
+            .is_type_mismatch());
+        assert!(check("const a = len(1)\n").unwrap_err().is_type_mismatch());
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        let document = Parser::parse("const a = 3\nconst c = c\n").unwrap();
+        assert!(document
+            .evaluate("a / (a - 3)")
+            .unwrap_err()
+            .is_division_by_zero());
+        assert!(document
+            .evaluate("9223372036854775807 + a")
+            .unwrap_err()
+            .is_arithmetic_overflow());
+        let error = document.evaluate("a + missing").unwrap_err();
+        assert_eq!(error.get_unresolved_name().unwrap().name, "missing");
+        assert_eq!(error.span().unwrap().lo.column, 4);
+        assert!(document.evaluate("c").unwrap_err().is_cyclic_definition());
+    }
+
+    // Every constant refers twice to the previous one, this only finishes
+    // if each constant is computed once.
+    #[test]
+    fn shared_references() {
+        let mut source = "const a0 = 1\n".to_owned();
+        for i in 1..=40 {
+            source.push_str(&format!("const a{} = a{} + a{}\n", i, i - 1, i - 1));
+        }
+        let document = Parser::parse(&source).unwrap();
+        assert!(document.type_check().is_ok());
+        assert_eq!(document.evaluate("a40").unwrap(), Value::Integer(1 << 40));
+    }
+
+    #[test]
+    fn cyclic_record_evaluation() {
+        let sources = [
+            "const p\n WS = p\n",
+            "const p\n WS = p.WS\n",
+            "const p\n WS = q\nconst q = p.WS\n",
+        ];
+        for source in sources.iter() {
+            let document = Parser::parse(source).unwrap();
+            assert!(document.evaluate("p").unwrap_err().is_cyclic_definition());
+        }
     }
 }
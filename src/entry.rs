@@ -0,0 +1,246 @@
+use crate::constant::ConstantDeclaration;
+use crate::cost::check_cost;
+use crate::error::*;
+use crate::expression::*;
+use crate::requirement::*;
+use crate::serialization::*;
+use crate::symbol_table::*;
+use crate::token::Span;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryField {
+    name: String,
+    min: i64,
+    max: i64,
+    initializer: Option<Expression>,
+    fields: Vec<ConstantDeclaration>,
+    span: Span,
+}
+
+impl Serializable for EntryField {
+    fn serialize(
+        &self,
+        f: &mut Formatter,
+        ctx: &SerializationContext,
+    ) -> Result<(), ::std::fmt::Error> {
+        indent(f, ctx.indent)?;
+        if self.min == self.max {
+            write!(f, "{} {}", self.min, self.name)?;
+        } else {
+            write!(f, "{}-{} {}", self.min, self.max, self.name)?;
+        }
+        if let Some(initializer) = &self.initializer {
+            write!(f, " = ")?;
+            initializer.serialize(f, ctx)?;
+        }
+        writeln!(f)?;
+
+        for field in &self.fields {
+            field.serialize(f, &ctx.indented().emitting_const())?;
+        }
+        return Ok(());
+    }
+}
+
+impl Display for EntryField {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return self.serialize(f, &SerializationContext::new());
+    }
+}
+
+impl EntryField {
+    pub fn new(
+        name: String,
+        min: i64,
+        max: i64,
+        initializer: Option<Expression>,
+        span: Span,
+    ) -> EntryField {
+        let fields = Vec::new();
+        return EntryField {
+            name,
+            min,
+            max,
+            initializer,
+            fields,
+            span,
+        };
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn span(&self) -> Span {
+        return self.span;
+    }
+
+    pub fn name(&self) -> &String {
+        return &self.name;
+    }
+
+    pub fn min(&self) -> i64 {
+        return self.min;
+    }
+
+    pub fn max(&self) -> i64 {
+        return self.max;
+    }
+
+    pub fn get_initializer(&self) -> Option<&Expression> {
+        return self.initializer.as_ref();
+    }
+
+    pub fn get_fields_mut(&mut self) -> &mut Vec<ConstantDeclaration> {
+        return (self.fields).as_mut();
+    }
+
+    pub fn get_fields(&self) -> &Vec<ConstantDeclaration> {
+        return &self.fields;
+    }
+
+    pub fn get_field(&self, i: usize) -> Option<&ConstantDeclaration> {
+        return self.get_fields().get(i);
+    }
+
+    pub fn get_field_mut(&mut self, i: usize) -> Option<&mut ConstantDeclaration> {
+        return self.get_fields_mut().get_mut(i);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryDeclaration {
+    name: String,
+    constants: Vec<ConstantDeclaration>,
+    fields: Vec<EntryField>,
+    requirements: Vec<RequirementDeclaration>,
+    span: Span,
+}
+
+impl Serializable for EntryDeclaration {
+    fn serialize(
+        &self,
+        f: &mut Formatter,
+        ctx: &SerializationContext,
+    ) -> Result<(), ::std::fmt::Error> {
+        indent(f, ctx.indent)?;
+        writeln!(f, "entry {}", self.name)?;
+        for constant in &self.constants {
+            constant.serialize(f, &ctx.indented())?;
+        }
+        for field in &self.fields {
+            field.serialize(f, &ctx.indented())?;
+        }
+        for requirement in &self.requirements {
+            requirement.serialize(f, &ctx.indented())?;
+        }
+        writeln!(f)?;
+        return Ok(());
+    }
+}
+
+impl Display for EntryDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return self.serialize(f, &SerializationContext::new());
+    }
+}
+
+impl EntryDeclaration {
+    pub fn new(name: String, span: Span) -> EntryDeclaration {
+        let constants = Vec::new();
+        let fields = Vec::new();
+        let requirements = Vec::new();
+        return EntryDeclaration {
+            name,
+            constants,
+            fields,
+            requirements,
+            span,
+        };
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn span(&self) -> Span {
+        return self.span;
+    }
+
+    pub fn name(&self) -> &String {
+        return &self.name;
+    }
+
+    pub fn get_constants_mut(&mut self) -> &mut Vec<ConstantDeclaration> {
+        return (self.constants).as_mut();
+    }
+
+    pub fn get_constants(&self) -> &Vec<ConstantDeclaration> {
+        return &self.constants;
+    }
+
+    pub fn get_fields_mut(&mut self) -> &mut Vec<EntryField> {
+        return (self.fields).as_mut();
+    }
+
+    pub fn get_fields(&self) -> &Vec<EntryField> {
+        return &self.fields;
+    }
+
+    pub fn get_field(&self, i: usize) -> Option<&EntryField> {
+        return self.get_fields().get(i);
+    }
+
+    pub fn get_field_mut(&mut self, i: usize) -> Option<&mut EntryField> {
+        return self.get_fields_mut().get_mut(i);
+    }
+
+    pub fn get_requirements_mut(&mut self) -> &mut Vec<RequirementDeclaration> {
+        return (self.requirements).as_mut();
+    }
+
+    pub fn get_requirements(&self) -> &Vec<RequirementDeclaration> {
+        return &self.requirements;
+    }
+
+    // The scope of the requirements, where constants and fields of the
+    // entry are visible.
+    pub fn make_scope<'a>(
+        &'a self,
+        table: &'a SymbolTable,
+    ) -> Result<SymbolTable<'a>, ParserError> {
+        let mut scope = table.make_child();
+        for constant in &self.constants {
+            scope.insert_symbol(constant.name(), Symbol::Const(constant))?;
+        }
+        for field in &self.fields {
+            scope.insert_symbol(field.name(), Symbol::Field(field))?;
+        }
+        return Ok(scope);
+    }
+
+    pub fn type_check(&self, table: &SymbolTable) -> Result<(), ParserError> {
+        let scope = self.make_scope(table)?;
+        for constant in &self.constants {
+            constant.type_check(&scope)?;
+            check_cost(constant, &scope)?;
+        }
+
+        for field in &self.fields {
+            if let Some(initializer) = field.get_initializer() {
+                initializer.type_check(&scope)?;
+            }
+            for constant in field.get_fields() {
+                constant.type_check(&scope)?;
+                check_cost(constant, &scope)?;
+            }
+        }
+
+        for requirement in &self.requirements {
+            requirement.type_check(&scope)?;
+        }
+        Ok(())
+    }
+}
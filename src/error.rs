@@ -11,7 +11,11 @@ pub struct IndentationError {
 
 impl Display for IndentationError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-        return write!(f, "{}", self);
+        return write!(
+            f,
+            "line started with {} whitespaces, expected {}",
+            self.actual, self.expected
+        );
     }
 }
 
@@ -21,12 +25,26 @@ impl Error for IndentationError {
     }
 }
 
+fn write_expected(f: &mut std::fmt::Formatter, expected: &[String]) -> std::fmt::Result {
+    return match expected.len() {
+        0 => Ok(()),
+        1 => write!(f, "expected {}, ", expected[0]),
+        _ => write!(f, "expected one of {}, ", expected.join(", ")),
+    };
+}
+
 #[derive(Debug, Clone)]
-pub struct EndOfTokenStreamError {}
+pub struct EndOfTokenStreamError {
+    pub expected: Vec<String>,
+}
 
 impl Display for EndOfTokenStreamError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-        return write!(f, "{}", self);
+        if self.expected.is_empty() {
+            return write!(f, "unexpected end of file");
+        }
+        write_expected(f, &self.expected)?;
+        return write!(f, "found end of file");
     }
 }
 
@@ -39,11 +57,26 @@ impl Error for EndOfTokenStreamError {
 #[derive(Debug, Clone)]
 pub struct UnexpectedTokenError {
     token: Token,
+    expected: Vec<String>,
+}
+
+impl UnexpectedTokenError {
+    pub fn token(&self) -> &Token {
+        return &self.token;
+    }
+
+    pub fn expected(&self) -> &Vec<String> {
+        return &self.expected;
+    }
 }
 
 impl Display for UnexpectedTokenError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-        return write!(f, "{}", self);
+        if self.expected.is_empty() {
+            return write!(f, "unexpected {}", self.token.kind.describe());
+        }
+        write_expected(f, &self.expected)?;
+        return write!(f, "found {}", self.token.kind.describe());
     }
 }
 
@@ -53,20 +86,377 @@ impl Error for UnexpectedTokenError {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct InvalidCharacterError {
+    pub span: Span,
+    pub character: String,
+}
+
+impl Display for InvalidCharacterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "invalid character `{}`", self.character);
+    }
+}
+
+impl Error for InvalidCharacterError {
+    fn description(&self) -> &str {
+        return "character that cannot start any token";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IntegerOverflowError {
+    pub span: Span,
+    pub text: String,
+}
+
+impl Display for IntegerOverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "integer {} is out of range", self.text);
+    }
+}
+
+impl Error for IntegerOverflowError {
+    fn description(&self) -> &str {
+        return "integer literal does not fit in 64 bits";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnresolvedNameError {
+    pub span: Span,
+    pub name: String,
+}
+
+impl Display for UnresolvedNameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "cannot find `{}` in this scope", self.name);
+    }
+}
+
+impl Error for UnresolvedNameError {
+    fn description(&self) -> &str {
+        return "name does not refer to any declaration";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeMismatchError {
+    pub span: Span,
+    pub expected: String,
+    pub found: String,
+}
+
+impl Display for TypeMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "expected {}, found {}", self.expected, self.found);
+    }
+}
+
+impl Error for TypeMismatchError {
+    fn description(&self) -> &str {
+        return "expression has the wrong type";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CyclicDefinitionError {
+    pub span: Span,
+    pub name: String,
+}
+
+impl Display for CyclicDefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "`{}` is defined in terms of itself", self.name);
+    }
+}
+
+impl Error for CyclicDefinitionError {
+    fn description(&self) -> &str {
+        return "constant depends on its own value";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateDeclarationError {
+    pub span: Span,
+    pub previous: Span,
+    pub name: String,
+}
+
+impl Display for DuplicateDeclarationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(
+            f,
+            "`{}` is declared more than once in the same scope",
+            self.name
+        );
+    }
+}
+
+impl Error for DuplicateDeclarationError {
+    fn description(&self) -> &str {
+        return "two declarations share a name";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShadowedDeclarationError {
+    pub span: Span,
+    pub previous: Span,
+    pub name: String,
+}
+
+impl Display for ShadowedDeclarationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "`{}` shadows a declaration of an outer scope", self.name);
+    }
+}
+
+impl Error for ShadowedDeclarationError {
+    fn description(&self) -> &str {
+        return "declaration hides one of an outer scope";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DivisionByZeroError {
+    pub span: Span,
+}
+
+impl Display for DivisionByZeroError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "division by zero");
+    }
+}
+
+impl Error for DivisionByZeroError {
+    fn description(&self) -> &str {
+        return "expression divides by zero";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArithmeticOverflowError {
+    pub span: Span,
+}
+
+impl Display for ArithmeticOverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "arithmetic operation overflows");
+    }
+}
+
+impl Error for ArithmeticOverflowError {
+    fn description(&self) -> &str {
+        return "result does not fit in its type";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnknownMemberError {
+    pub span: Span,
+    pub name: String,
+    pub found: String,
+}
+
+impl Display for UnknownMemberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "no member `{}` in {}", self.name, self.found);
+    }
+}
+
+impl Error for UnknownMemberError {
+    fn description(&self) -> &str {
+        return "unknown member";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArityMismatchError {
+    pub span: Span,
+    pub name: String,
+    pub expected: String,
+    pub found: usize,
+}
+
+impl Display for ArityMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(
+            f,
+            "`{}` expects {}, found {}",
+            self.name, self.expected, self.found
+        );
+    }
+}
+
+impl Error for ArityMismatchError {
+    fn description(&self) -> &str {
+        return "wrong number of arguments";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvalidJsonError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Display for InvalidJsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "invalid JSON: {}", self.message);
+    }
+}
+
+impl Error for InvalidJsonError {
+    fn description(&self) -> &str {
+        return "The text is not valid JSON";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvalidJsonDocumentError {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for InvalidJsonDocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "invalid document at `{}`: {}", self.path, self.message);
+    }
+}
+
+impl Error for InvalidJsonDocumentError {
+    fn description(&self) -> &str {
+        return "The JSON does not describe a document";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FloatOverflowError {
+    pub span: Span,
+    pub text: String,
+}
+
+impl Display for FloatOverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "float {} is out of range", self.text);
+    }
+}
+
+impl Error for FloatOverflowError {
+    fn description(&self) -> &str {
+        return "float literal does not fit in 64 bits";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvalidBoundsError {
+    pub span: Span,
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Display for InvalidBoundsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(
+            f,
+            "upper bound {} is less than lower bound {}",
+            self.max, self.min
+        );
+    }
+}
+
+impl Error for InvalidBoundsError {
+    fn description(&self) -> &str {
+        return "field bounds are inverted";
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvalidEscapeError {
+    pub span: Span,
+    pub sequence: String,
+}
+
+impl Display for InvalidEscapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "unknown escape sequence `{}` in string", self.sequence);
+    }
+}
+
+impl Error for InvalidEscapeError {
+    fn description(&self) -> &str {
+        return "strings only escape quotes, backslashes and new lines";
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ParserError {
     EndOfTokenStream(EndOfTokenStreamError),
     Indentation(IndentationError),
     UnexpectedToken(UnexpectedTokenError),
+    InvalidCharacter(InvalidCharacterError),
+    IntegerOverflow(IntegerOverflowError),
+    UnresolvedName(UnresolvedNameError),
+    TypeMismatch(TypeMismatchError),
+    CyclicDefinition(CyclicDefinitionError),
+    DuplicateDeclaration(DuplicateDeclarationError),
+    ShadowedDeclaration(ShadowedDeclarationError),
+    DivisionByZero(DivisionByZeroError),
+    ArithmeticOverflow(ArithmeticOverflowError),
+    UnknownMember(UnknownMemberError),
+    ArityMismatch(ArityMismatchError),
+    InvalidJson(InvalidJsonError),
+    InvalidJsonDocument(InvalidJsonDocumentError),
+    FloatOverflow(FloatOverflowError),
+    InvalidBounds(InvalidBoundsError),
+    InvalidEscape(InvalidEscapeError),
 }
 
 impl ParserError {
     pub fn new_unexpected_token(token: Token) -> ParserError {
-        return ParserError::UnexpectedToken(UnexpectedTokenError { token });
+        let expected = Vec::new();
+        return ParserError::UnexpectedToken(UnexpectedTokenError { token, expected });
+    }
+
+    pub fn new_expected_token(token: Token, expected: Vec<String>) -> ParserError {
+        return ParserError::UnexpectedToken(UnexpectedTokenError { token, expected });
     }
 
     pub fn new_end_of_token_stream() -> ParserError {
-        return ParserError::EndOfTokenStream(EndOfTokenStreamError {});
+        let expected = Vec::new();
+        return ParserError::EndOfTokenStream(EndOfTokenStreamError { expected });
+    }
+
+    pub fn new_unexpected_end_of_token_stream(expected: Vec<String>) -> ParserError {
+        return ParserError::EndOfTokenStream(EndOfTokenStreamError { expected });
+    }
+
+    // The end of the token stream has no location, callers are expected to
+    // point at the end of the source instead.
+    pub fn span(&self) -> Option<Span> {
+        return match self {
+            ParserError::EndOfTokenStream(_) => None,
+            ParserError::Indentation(error) => Some(error.span),
+            ParserError::UnexpectedToken(error) => Some(error.token.span),
+            ParserError::InvalidCharacter(error) => Some(error.span),
+            ParserError::IntegerOverflow(error) => Some(error.span),
+            ParserError::UnresolvedName(error) => Some(error.span),
+            ParserError::TypeMismatch(error) => Some(error.span),
+            ParserError::CyclicDefinition(error) => Some(error.span),
+            ParserError::DuplicateDeclaration(error) => Some(error.span),
+            ParserError::ShadowedDeclaration(error) => Some(error.span),
+            ParserError::DivisionByZero(error) => Some(error.span),
+            ParserError::ArithmeticOverflow(error) => Some(error.span),
+            ParserError::UnknownMember(error) => Some(error.span),
+            ParserError::ArityMismatch(error) => Some(error.span),
+            ParserError::InvalidJson(error) => Some(error.span),
+            ParserError::InvalidJsonDocument(_) => None,
+            ParserError::FloatOverflow(error) => Some(error.span),
+            ParserError::InvalidBounds(error) => Some(error.span),
+            ParserError::InvalidEscape(error) => Some(error.span),
+        };
     }
 
     pub fn new_indentation_miss_match(span: Span, expected: i64, actual: i64) -> ParserError {
@@ -77,6 +467,92 @@ impl ParserError {
         });
     }
 
+    pub fn new_invalid_character(span: Span, character: String) -> ParserError {
+        return ParserError::InvalidCharacter(InvalidCharacterError { span, character });
+    }
+
+    pub fn new_integer_overflow(span: Span, text: String) -> ParserError {
+        return ParserError::IntegerOverflow(IntegerOverflowError { span, text });
+    }
+
+    pub fn new_unresolved_name(span: Span, name: String) -> ParserError {
+        return ParserError::UnresolvedName(UnresolvedNameError { span, name });
+    }
+
+    pub fn new_type_mismatch(span: Span, expected: String, found: String) -> ParserError {
+        return ParserError::TypeMismatch(TypeMismatchError {
+            span,
+            expected,
+            found,
+        });
+    }
+
+    pub fn new_cyclic_definition(span: Span, name: String) -> ParserError {
+        return ParserError::CyclicDefinition(CyclicDefinitionError { span, name });
+    }
+
+    pub fn new_duplicate_declaration(span: Span, previous: Span, name: String) -> ParserError {
+        return ParserError::DuplicateDeclaration(DuplicateDeclarationError {
+            span,
+            previous,
+            name,
+        });
+    }
+
+    pub fn new_shadowed_declaration(span: Span, previous: Span, name: String) -> ParserError {
+        return ParserError::ShadowedDeclaration(ShadowedDeclarationError {
+            span,
+            previous,
+            name,
+        });
+    }
+
+    pub fn new_division_by_zero(span: Span) -> ParserError {
+        return ParserError::DivisionByZero(DivisionByZeroError { span });
+    }
+
+    pub fn new_arithmetic_overflow(span: Span) -> ParserError {
+        return ParserError::ArithmeticOverflow(ArithmeticOverflowError { span });
+    }
+
+    pub fn new_unknown_member(span: Span, name: String, found: String) -> ParserError {
+        return ParserError::UnknownMember(UnknownMemberError { span, name, found });
+    }
+
+    pub fn new_arity_mismatch(
+        span: Span,
+        name: String,
+        expected: String,
+        found: usize,
+    ) -> ParserError {
+        return ParserError::ArityMismatch(ArityMismatchError {
+            span,
+            name,
+            expected,
+            found,
+        });
+    }
+
+    pub fn new_invalid_json(span: Span, message: String) -> ParserError {
+        return ParserError::InvalidJson(InvalidJsonError { span, message });
+    }
+
+    pub fn new_invalid_json_document(path: String, message: String) -> ParserError {
+        return ParserError::InvalidJsonDocument(InvalidJsonDocumentError { path, message });
+    }
+
+    pub fn new_float_overflow(span: Span, text: String) -> ParserError {
+        return ParserError::FloatOverflow(FloatOverflowError { span, text });
+    }
+
+    pub fn new_invalid_bounds(span: Span, min: i64, max: i64) -> ParserError {
+        return ParserError::InvalidBounds(InvalidBoundsError { span, min, max });
+    }
+
+    pub fn new_invalid_escape(span: Span, sequence: String) -> ParserError {
+        return ParserError::InvalidEscape(InvalidEscapeError { span, sequence });
+    }
+
     pub fn is_indentation_error(&self) -> bool {
         return matches!(self, ParserError::Indentation(_));
     }
@@ -89,6 +565,70 @@ impl ParserError {
         return matches!(self, ParserError::EndOfTokenStream(_));
     }
 
+    pub fn is_invalid_character(&self) -> bool {
+        return matches!(self, ParserError::InvalidCharacter(_));
+    }
+
+    pub fn is_integer_overflow(&self) -> bool {
+        return matches!(self, ParserError::IntegerOverflow(_));
+    }
+
+    pub fn is_unresolved_name(&self) -> bool {
+        return matches!(self, ParserError::UnresolvedName(_));
+    }
+
+    pub fn is_type_mismatch(&self) -> bool {
+        return matches!(self, ParserError::TypeMismatch(_));
+    }
+
+    pub fn is_cyclic_definition(&self) -> bool {
+        return matches!(self, ParserError::CyclicDefinition(_));
+    }
+
+    pub fn is_duplicate_declaration(&self) -> bool {
+        return matches!(self, ParserError::DuplicateDeclaration(_));
+    }
+
+    pub fn is_shadowed_declaration(&self) -> bool {
+        return matches!(self, ParserError::ShadowedDeclaration(_));
+    }
+
+    pub fn is_division_by_zero(&self) -> bool {
+        return matches!(self, ParserError::DivisionByZero(_));
+    }
+
+    pub fn is_arithmetic_overflow(&self) -> bool {
+        return matches!(self, ParserError::ArithmeticOverflow(_));
+    }
+
+    pub fn is_unknown_member(&self) -> bool {
+        return matches!(self, ParserError::UnknownMember(_));
+    }
+
+    pub fn is_arity_mismatch(&self) -> bool {
+        return matches!(self, ParserError::ArityMismatch(_));
+    }
+
+    pub fn is_invalid_json(&self) -> bool {
+        return matches!(self, ParserError::InvalidJson(_));
+    }
+
+    pub fn is_invalid_json_document(&self) -> bool {
+        return matches!(self, ParserError::InvalidJsonDocument(_));
+    }
+
+    pub fn is_float_overflow(&self) -> bool {
+        return matches!(self, ParserError::FloatOverflow(_));
+    }
+
+    pub fn is_invalid_bounds(&self) -> bool {
+        return matches!(self, ParserError::InvalidBounds(_));
+    }
+
+    pub fn is_invalid_escape(&self) -> bool {
+        return matches!(self, ParserError::InvalidEscape(_));
+    }
+
     pub fn get_indentation_error(&self) -> Option<&IndentationError> {
         return match self {
             ParserError::Indentation(error) => Some(error),
@@ -109,6 +649,118 @@ impl ParserError {
             _ => None,
         };
     }
+
+    pub fn get_invalid_character(&self) -> Option<&InvalidCharacterError> {
+        return match self {
+            ParserError::InvalidCharacter(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_integer_overflow(&self) -> Option<&IntegerOverflowError> {
+        return match self {
+            ParserError::IntegerOverflow(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_unresolved_name(&self) -> Option<&UnresolvedNameError> {
+        return match self {
+            ParserError::UnresolvedName(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_type_mismatch(&self) -> Option<&TypeMismatchError> {
+        return match self {
+            ParserError::TypeMismatch(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_cyclic_definition(&self) -> Option<&CyclicDefinitionError> {
+        return match self {
+            ParserError::CyclicDefinition(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_duplicate_declaration(&self) -> Option<&DuplicateDeclarationError> {
+        return match self {
+            ParserError::DuplicateDeclaration(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_shadowed_declaration(&self) -> Option<&ShadowedDeclarationError> {
+        return match self {
+            ParserError::ShadowedDeclaration(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_division_by_zero(&self) -> Option<&DivisionByZeroError> {
+        return match self {
+            ParserError::DivisionByZero(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_arithmetic_overflow(&self) -> Option<&ArithmeticOverflowError> {
+        return match self {
+            ParserError::ArithmeticOverflow(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_unknown_member(&self) -> Option<&UnknownMemberError> {
+        return match self {
+            ParserError::UnknownMember(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_arity_mismatch(&self) -> Option<&ArityMismatchError> {
+        return match self {
+            ParserError::ArityMismatch(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_invalid_json(&self) -> Option<&InvalidJsonError> {
+        return match self {
+            ParserError::InvalidJson(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_invalid_json_document(&self) -> Option<&InvalidJsonDocumentError> {
+        return match self {
+            ParserError::InvalidJsonDocument(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_float_overflow(&self) -> Option<&FloatOverflowError> {
+        return match self {
+            ParserError::FloatOverflow(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_invalid_bounds(&self) -> Option<&InvalidBoundsError> {
+        return match self {
+            ParserError::InvalidBounds(error) => Some(error),
+            _ => None,
+        };
+    }
+
+    pub fn get_invalid_escape(&self) -> Option<&InvalidEscapeError> {
+        return match self {
+            ParserError::InvalidEscape(error) => Some(error),
+            _ => None,
+        };
+    }
 }
 
 impl Display for ParserError {
@@ -117,16 +769,24 @@ impl Display for ParserError {
             ParserError::EndOfTokenStream(content) => content.fmt(f),
             ParserError::Indentation(content) => content.fmt(f),
             ParserError::UnexpectedToken(content) => content.fmt(f),
+            ParserError::InvalidCharacter(content) => content.fmt(f),
+            ParserError::IntegerOverflow(content) => content.fmt(f),
+            ParserError::UnresolvedName(content) => content.fmt(f),
+            ParserError::TypeMismatch(content) => content.fmt(f),
+            ParserError::CyclicDefinition(content) => content.fmt(f),
+            ParserError::DuplicateDeclaration(content) => content.fmt(f),
+            ParserError::ShadowedDeclaration(content) => content.fmt(f),
+            ParserError::DivisionByZero(content) => content.fmt(f),
+            ParserError::ArithmeticOverflow(content) => content.fmt(f),
+            ParserError::UnknownMember(content) => content.fmt(f),
+            ParserError::ArityMismatch(content) => content.fmt(f),
+            ParserError::InvalidJson(content) => content.fmt(f),
+            ParserError::InvalidJsonDocument(content) => content.fmt(f),
+            ParserError::FloatOverflow(content) => content.fmt(f),
+            ParserError::InvalidBounds(content) => content.fmt(f),
+            ParserError::InvalidEscape(content) => content.fmt(f),
         };
     }
 }
 
-impl Error for ParserError {
-    fn description(&self) -> &str {
-        return match self {
-            ParserError::EndOfTokenStream(content) => content.description(),
-            ParserError::Indentation(content) => content.description(),
-            ParserError::UnexpectedToken(content) => content.description(),
-        };
-    }
-}
+impl Error for ParserError {}
@@ -1,7 +1,12 @@
+use crate::builtin::*;
+use crate::constant::ConstantDeclaration;
+use crate::error::*;
 use crate::serialization::*;
+use crate::symbol_table::*;
 use crate::token::Span;
+use crate::types::*;
+use crate::value::*;
 use core::fmt::Display;
-use core::fmt::Formatter;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
@@ -9,30 +14,51 @@ pub enum Literal {
     Str(String),
     Float(f64),
     Indent(String),
+    List(Vec<Expression>),
 }
 
 impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
         return match self {
             Literal::Integer(i) => write!(f, "Literal {}", i),
-            Literal::Str(s) => write!(f, "Literal \"{}\"", s),
+            Literal::Str(s) => write!(f, "Literal \"{}\"", escape(s)),
             Literal::Float(v) => write!(f, "Literal {}", v),
             Literal::Indent(v) => write!(f, "Literal {}", v),
+            Literal::List(v) => {
+                write!(f, "Literal ")?;
+                serialize_list(f, v, &SerializationContext::new())
+            }
         };
     }
 }
 
+fn serialize_list(
+    f: &mut std::fmt::Formatter,
+    elements: &[Expression],
+    ctx: &SerializationContext,
+) -> Result<(), ::std::fmt::Error> {
+    write!(f, "[")?;
+    for (i, element) in elements.iter().enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        element.serialize(f, ctx)?;
+    }
+    return write!(f, "]");
+}
+
 impl Serializable for Literal {
     fn serialize(
         &self,
         f: &mut std::fmt::Formatter,
-        ctx: &SerializationContext,
+        _ctx: &SerializationContext,
     ) -> Result<(), ::std::fmt::Error> {
         return match self {
             Literal::Integer(i) => write!(f, "{}", i),
-            Literal::Str(s) => write!(f, "\"{}\"", s),
-            Literal::Float(v) => write!(f, "{}", v),
+            Literal::Str(s) => write!(f, "\"{}\"", escape(s)),
+            Literal::Float(v) => write!(f, "{:?}", v),
             Literal::Indent(v) => write!(f, "{}", v),
+            Literal::List(v) => serialize_list(f, v, _ctx),
         };
     }
 }
@@ -51,33 +77,103 @@ pub enum BinaryExpressionKind {
     Greater,
     GreaterEqual,
     Different,
+    In,
+}
+
+impl BinaryExpressionKind {
+    pub fn symbol(&self) -> &'static str {
+        return match self {
+            BinaryExpressionKind::Add => "+",
+            BinaryExpressionKind::Sub => "-",
+            BinaryExpressionKind::Mult => "*",
+            BinaryExpressionKind::Div => "/",
+            BinaryExpressionKind::Or => "or",
+            BinaryExpressionKind::And => "and",
+            BinaryExpressionKind::Equal => "==",
+            BinaryExpressionKind::Less => "<",
+            BinaryExpressionKind::LessEqual => "<=",
+            BinaryExpressionKind::GreaterEqual => ">=",
+            BinaryExpressionKind::Greater => ">",
+            BinaryExpressionKind::Different => "!=",
+            BinaryExpressionKind::In => "in",
+        };
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<BinaryExpressionKind> {
+        return BINARY_OPERATORS
+            .iter()
+            .find(|x| x.symbol() == symbol)
+            .cloned();
+    }
+
+    // How tightly the operator binds, following the precedence chain of
+    // the parser. Higher values bind tighter.
+    pub fn precedence(&self) -> u8 {
+        return match self {
+            BinaryExpressionKind::Or => 1,
+            BinaryExpressionKind::And => 2,
+            BinaryExpressionKind::Equal | BinaryExpressionKind::Different => 4,
+            BinaryExpressionKind::Less
+            | BinaryExpressionKind::LessEqual
+            | BinaryExpressionKind::Greater
+            | BinaryExpressionKind::GreaterEqual
+            | BinaryExpressionKind::In => 5,
+            BinaryExpressionKind::Add | BinaryExpressionKind::Sub => 6,
+            BinaryExpressionKind::Mult | BinaryExpressionKind::Div => 7,
+        };
+    }
+}
+
+pub const BINARY_OPERATORS: [BinaryExpressionKind; 13] = [
+    BinaryExpressionKind::Add,
+    BinaryExpressionKind::Sub,
+    BinaryExpressionKind::Mult,
+    BinaryExpressionKind::Div,
+    BinaryExpressionKind::Or,
+    BinaryExpressionKind::And,
+    BinaryExpressionKind::Equal,
+    BinaryExpressionKind::Less,
+    BinaryExpressionKind::LessEqual,
+    BinaryExpressionKind::Greater,
+    BinaryExpressionKind::GreaterEqual,
+    BinaryExpressionKind::Different,
+    BinaryExpressionKind::In,
+];
+
+const NOT_PRECEDENCE: u8 = 3;
+const NEG_PRECEDENCE: u8 = 8;
+const POSTFIX_PRECEDENCE: u8 = 9;
+const PRIMARY_PRECEDENCE: u8 = 10;
+
+// Serializes an operand, wrapped in parentheses only when it binds looser
+// than the operator it belongs to.
+fn serialize_operand(
+    f: &mut std::fmt::Formatter,
+    operand: &Expression,
+    precedence: u8,
+    ctx: &SerializationContext,
+) -> Result<(), ::std::fmt::Error> {
+    if operand.content.precedence() >= precedence {
+        return operand.serialize(f, ctx);
+    }
+    write!(f, "(")?;
+    operand.serialize(f, ctx)?;
+    return write!(f, ")");
 }
 
 impl Serializable for BinaryExpressionKind {
     fn serialize(
         &self,
         f: &mut std::fmt::Formatter,
-        ctx: &SerializationContext,
+        _ctx: &SerializationContext,
     ) -> Result<(), ::std::fmt::Error> {
-        match self {
-            BinaryExpressionKind::Add => write!(f, "+"),
-            BinaryExpressionKind::Sub => write!(f, "-"),
-            BinaryExpressionKind::Mult => write!(f, "*"),
-            BinaryExpressionKind::Div => write!(f, "/"),
-            BinaryExpressionKind::Or => write!(f, "or"),
-            BinaryExpressionKind::And => write!(f, "and"),
-            BinaryExpressionKind::Equal => write!(f, "=="),
-            BinaryExpressionKind::Less => write!(f, "<"),
-            BinaryExpressionKind::LessEqual => write!(f, "<="),
-            BinaryExpressionKind::GreaterEqual => write!(f, ">="),
-            BinaryExpressionKind::Greater => write!(f, ">"),
-            BinaryExpressionKind::Different => write!(f, "!="),
-        }
+        return write!(f, "{}", self.symbol());
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum UnaryExpressionKind {
+    Neg,
     Not,
 }
 
@@ -85,10 +181,11 @@ impl Serializable for UnaryExpressionKind {
     fn serialize(
         &self,
         f: &mut std::fmt::Formatter,
-        ctx: &SerializationContext,
+        _ctx: &SerializationContext,
     ) -> Result<(), ::std::fmt::Error> {
         match self {
-            UnaryExpressionKind::Not => write!(f, "-"),
+            UnaryExpressionKind::Neg => write!(f, "-"),
+            UnaryExpressionKind::Not => write!(f, "not "),
         }
     }
 }
@@ -98,6 +195,8 @@ pub enum ExpressionEnum {
     Lit(Literal),
     Una(UnaryExpressionKind, Box<Expression>),
     Bin(BinaryExpressionKind, Box<Expression>, Box<Expression>),
+    Member(Box<Expression>, String),
+    Call(String, Vec<Expression>),
 }
 
 impl Serializable for ExpressionEnum {
@@ -110,18 +209,30 @@ impl Serializable for ExpressionEnum {
             ExpressionEnum::Lit(literal) => literal.serialize(f, ctx),
             ExpressionEnum::Una(kind, exp) => {
                 kind.serialize(f, ctx)?;
-                exp.serialize(f, ctx)?;
-                Ok(())
+                serialize_operand(f, exp, self.precedence(), ctx)
             }
             ExpressionEnum::Bin(kind, lhs, rhs) => {
-                write!(f, "(")?;
-                lhs.serialize(f, ctx)?;
+                // Operators are left associative, so `a - (b - c)` needs
+                // its parentheses while `(a - b) - c` does not.
+                serialize_operand(f, lhs, kind.precedence(), ctx)?;
                 write!(f, " ")?;
                 kind.serialize(f, ctx)?;
                 write!(f, " ")?;
-                rhs.serialize(f, ctx)?;
-                write!(f, ")")?;
-                Ok(())
+                serialize_operand(f, rhs, kind.precedence() + 1, ctx)
+            }
+            ExpressionEnum::Member(exp, member) => {
+                serialize_operand(f, exp, self.precedence(), ctx)?;
+                write!(f, ".{}", member)
+            }
+            ExpressionEnum::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    arg.serialize(f, ctx)?;
+                }
+                write!(f, ")")
             }
         };
     }
@@ -134,6 +245,16 @@ pub struct Expression {
 }
 
 impl ExpressionEnum {
+    pub fn precedence(&self) -> u8 {
+        return match self {
+            ExpressionEnum::Lit(_) | ExpressionEnum::Call(_, _) => PRIMARY_PRECEDENCE,
+            ExpressionEnum::Una(UnaryExpressionKind::Neg, _) => NEG_PRECEDENCE,
+            ExpressionEnum::Una(UnaryExpressionKind::Not, _) => NOT_PRECEDENCE,
+            ExpressionEnum::Bin(kind, _, _) => kind.precedence(),
+            ExpressionEnum::Member(_, _) => POSTFIX_PRECEDENCE,
+        };
+    }
+
     pub fn new_bin(kind: BinaryExpressionKind, lhs: Expression, rhs: Expression) -> ExpressionEnum {
         return ExpressionEnum::Bin(kind, Box::new(lhs), Box::new(rhs));
     }
@@ -167,7 +288,11 @@ impl ExpressionEnum {
     }
 
     pub fn different(lhs: Expression, rhs: Expression) -> ExpressionEnum {
-        return ExpressionEnum::Bin(BinaryExpressionKind::Equal, Box::new(lhs), Box::new(rhs));
+        return ExpressionEnum::Bin(
+            BinaryExpressionKind::Different,
+            Box::new(lhs),
+            Box::new(rhs),
+        );
     }
 
     pub fn less(lhs: Expression, rhs: Expression) -> ExpressionEnum {
@@ -198,10 +323,22 @@ impl ExpressionEnum {
         );
     }
 
+    pub fn member(lhs: Expression, member: String) -> ExpressionEnum {
+        return ExpressionEnum::Member(Box::new(lhs), member);
+    }
+
+    pub fn call(name: String, args: Vec<Expression>) -> ExpressionEnum {
+        return ExpressionEnum::Call(name, args);
+    }
+
     pub fn new_una(kind: UnaryExpressionKind, lhs: Expression) -> ExpressionEnum {
         return ExpressionEnum::Una(kind, Box::new(lhs));
     }
 
+    pub fn neg(lhs: Expression) -> ExpressionEnum {
+        return ExpressionEnum::Una(UnaryExpressionKind::Neg, Box::new(lhs));
+    }
+
     pub fn not(lhs: Expression) -> ExpressionEnum {
         return ExpressionEnum::Una(UnaryExpressionKind::Not, Box::new(lhs));
     }
@@ -222,11 +359,17 @@ impl ExpressionEnum {
         return ExpressionEnum::Lit(Literal::Float(f));
     }
 
+    pub fn list(elements: Vec<Expression>) -> ExpressionEnum {
+        return ExpressionEnum::Lit(Literal::List(elements));
+    }
+
     pub fn arity(&self) -> usize {
         return match self {
             ExpressionEnum::Lit(_) => 0,
             ExpressionEnum::Una(_, _) => 1,
             ExpressionEnum::Bin(_, _, _) => 2,
+            ExpressionEnum::Member(_, _) => 1,
+            ExpressionEnum::Call(_, args) => args.len(),
         };
     }
 
@@ -235,6 +378,8 @@ impl ExpressionEnum {
             ExpressionEnum::Lit(_) => true,
             ExpressionEnum::Una(_, _) => false,
             ExpressionEnum::Bin(_, _, _) => false,
+            ExpressionEnum::Member(_, _) => false,
+            ExpressionEnum::Call(_, _) => false,
         };
     }
 
@@ -243,6 +388,8 @@ impl ExpressionEnum {
             ExpressionEnum::Lit(_) => false,
             ExpressionEnum::Una(_, _) => true,
             ExpressionEnum::Bin(_, _, _) => false,
+            ExpressionEnum::Member(_, _) => false,
+            ExpressionEnum::Call(_, _) => false,
         };
     }
 
@@ -251,6 +398,8 @@ impl ExpressionEnum {
             ExpressionEnum::Lit(_) => false,
             ExpressionEnum::Una(_, _) => false,
             ExpressionEnum::Bin(_, _, _) => true,
+            ExpressionEnum::Member(_, _) => false,
+            ExpressionEnum::Call(_, _) => false,
         };
     }
 
@@ -259,6 +408,8 @@ impl ExpressionEnum {
             ExpressionEnum::Lit(_) => None,
             ExpressionEnum::Una(_, left) => Some(left),
             ExpressionEnum::Bin(_, left, _) => Some(left),
+            ExpressionEnum::Member(left, _) => Some(left),
+            ExpressionEnum::Call(_, _) => None,
         };
     }
 
@@ -267,6 +418,8 @@ impl ExpressionEnum {
             ExpressionEnum::Lit(_) => None,
             ExpressionEnum::Una(_, _) => None,
             ExpressionEnum::Bin(_, _, right) => Some(right),
+            ExpressionEnum::Member(_, _) => None,
+            ExpressionEnum::Call(_, _) => None,
         };
     }
 
@@ -275,6 +428,8 @@ impl ExpressionEnum {
             ExpressionEnum::Lit(lit) => Some(lit),
             ExpressionEnum::Una(_, _) => None,
             ExpressionEnum::Bin(_, _, _) => None,
+            ExpressionEnum::Member(_, _) => None,
+            ExpressionEnum::Call(_, _) => None,
         };
     }
 
@@ -283,6 +438,22 @@ impl ExpressionEnum {
             ExpressionEnum::Lit(_) => None,
             ExpressionEnum::Una(_, _) => None,
             ExpressionEnum::Bin(kind, _, _) => Some(kind),
+            ExpressionEnum::Member(_, _) => None,
+            ExpressionEnum::Call(_, _) => None,
+        };
+    }
+
+    pub fn arguments(&self) -> Option<&Vec<Expression>> {
+        return match self {
+            ExpressionEnum::Call(_, args) => Some(args),
+            _ => None,
+        };
+    }
+
+    pub fn member_name(&self) -> Option<&String> {
+        return match self {
+            ExpressionEnum::Member(_, member) => Some(member),
+            _ => None,
         };
     }
 
@@ -291,6 +462,8 @@ impl ExpressionEnum {
             ExpressionEnum::Lit(_) => None,
             ExpressionEnum::Una(kind, _) => Some(kind),
             ExpressionEnum::Bin(_, _, _) => None,
+            ExpressionEnum::Member(_, _) => None,
+            ExpressionEnum::Call(_, _) => None,
         };
     }
 }
@@ -371,11 +544,26 @@ impl Expression {
         return Expression { content, span };
     }
 
+    pub fn member(lhs: Expression, member: String, span: Span) -> Expression {
+        let content = ExpressionEnum::member(lhs, member);
+        return Expression { content, span };
+    }
+
+    pub fn call(name: String, args: Vec<Expression>, span: Span) -> Expression {
+        let content = ExpressionEnum::call(name, args);
+        return Expression { content, span };
+    }
+
     pub fn new_una(kind: UnaryExpressionKind, lhs: Expression, span: Span) -> Expression {
         let content = ExpressionEnum::new_una(kind, lhs);
         return Expression { content, span };
     }
 
+    pub fn neg(lhs: Expression, span: Span) -> Expression {
+        let content = ExpressionEnum::neg(lhs);
+        return Expression { content, span };
+    }
+
     pub fn not(lhs: Expression, span: Span) -> Expression {
         let content = ExpressionEnum::not(lhs);
         return Expression { content, span };
@@ -401,6 +589,11 @@ impl Expression {
         return Expression { content, span };
     }
 
+    pub fn list(elements: Vec<Expression>, span: Span) -> Expression {
+        let content = ExpressionEnum::list(elements);
+        return Expression { content, span };
+    }
+
     pub fn arity(&self) -> usize {
         return self.content.arity();
     }
@@ -437,8 +630,20 @@ impl Expression {
         return self.content.unary_kind();
     }
 
+    pub fn member_name(&self) -> Option<&String> {
+        return self.content.member_name();
+    }
+
+    pub fn arguments(&self) -> Option<&Vec<Expression>> {
+        return self.content.arguments();
+    }
+
+    pub fn content(&self) -> &ExpressionEnum {
+        return &self.content;
+    }
+
     pub fn span(&self) -> Span {
-        return self.span.clone();
+        return self.span;
     }
 
     pub fn set_span(&mut self, span: Span) {
@@ -446,6 +651,362 @@ impl Expression {
     }
 }
 
+fn identifier_type(name: &str, span: Span, table: &SymbolTable) -> Result<Type, ParserError> {
+    return match table.get(name) {
+        None => Err(ParserError::new_unresolved_name(span, name.to_owned())),
+        Some(Symbol::Pending(_)) => Err(ParserError::new_cyclic_definition(span, name.to_owned())),
+        // Top level constants are resolved in the root table, where their
+        // type is computed once.
+        Some(Symbol::Const(constant)) if table.is_global(name) => {
+            let root = table.root();
+            root.memoized_type(name, span, || constant.infer_type(root))
+        }
+        Some(Symbol::Const(constant)) => constant.infer_type(table),
+        Some(Symbol::Field(field)) => match field.get_initializer() {
+            Some(initializer) => initializer.type_check(table),
+            None => Ok(Type::Selection),
+        },
+        Some(Symbol::Opt(_)) | Some(Symbol::Alt(_)) | Some(Symbol::Entry(_)) => Ok(Type::Selection),
+    };
+}
+
+fn lookup_builtin(name: &str, span: Span) -> Result<Builtin, ParserError> {
+    return Builtin::lookup(name)
+        .ok_or_else(|| ParserError::new_unresolved_name(span, name.to_owned()));
+}
+
+fn find_member<'a>(
+    fields: &'a [ConstantDeclaration],
+    name: &str,
+) -> Option<&'a ConstantDeclaration> {
+    let name = normalize_name(name);
+    return fields.iter().find(|x| normalize_name(x.name()) == name);
+}
+
+// The member of a constant declared in scope, with the scope its
+// initializer resolves in.
+fn constant_member<'a, 't>(
+    constant: &'a ConstantDeclaration,
+    name: &str,
+    scope: &'t SymbolTable<'a>,
+) -> Option<(&'a ConstantDeclaration, &'t SymbolTable<'a>)> {
+    return match constant.get_fields() {
+        Some(fields) => Some((find_member(fields, name)?, scope)),
+        None => constant.get_initializer()?.resolve_member(name, scope),
+    };
+}
+
+impl Expression {
+    // Finds the constant a member access refers to, and the scope that
+    // declares it, where the names of its initializer resolve. Constants
+    // whose initializer is a plain reference, such as
+    // `profile = marine profile`, are followed to the body they name. The
+    // base must have been checked for cycles before calling this.
+    fn resolve_member<'a, 't>(
+        &self,
+        name: &str,
+        table: &'t SymbolTable<'a>,
+    ) -> Option<(&'a ConstantDeclaration, &'t SymbolTable<'a>)> {
+        return match &self.content {
+            ExpressionEnum::Lit(Literal::Indent(base)) => {
+                let scope = table.declaring_scope(base)?;
+                match scope.get(base)? {
+                    Symbol::Const(constant) => constant_member(constant, name, scope),
+                    Symbol::Field(field) => find_member(field.get_fields(), name)
+                        .map(|member| (member, scope))
+                        .or_else(|| field.get_initializer()?.resolve_member(name, scope)),
+                    _ => None,
+                }
+            }
+            ExpressionEnum::Member(base, member) => {
+                let (constant, scope) = base.resolve_member(member, table)?;
+                constant_member(constant, name, scope)
+            }
+            _ => None,
+        };
+    }
+
+    pub fn type_check(&self, table: &SymbolTable) -> Result<Type, ParserError> {
+        return match &self.content {
+            ExpressionEnum::Lit(Literal::Integer(_)) => Ok(Type::Integer),
+            ExpressionEnum::Lit(Literal::Float(_)) => Ok(Type::Float),
+            ExpressionEnum::Lit(Literal::Str(_)) => Ok(Type::Str),
+            ExpressionEnum::Lit(Literal::Indent(name)) => identifier_type(name, self.span, table),
+            ExpressionEnum::Lit(Literal::List(elements)) => {
+                let mut element_type: Option<Type> = None;
+                for element in elements {
+                    let found = element.type_check(table)?;
+                    element_type = match element_type {
+                        None => Some(found),
+                        Some(previous) => match previous.unify(&found) {
+                            Some(unified) => Some(unified),
+                            None => {
+                                return Err(ParserError::new_type_mismatch(
+                                    element.span,
+                                    format!("list element of type {}", previous),
+                                    found.to_string(),
+                                ))
+                            }
+                        },
+                    };
+                }
+                Ok(Type::List(element_type.map(Box::new)))
+            }
+            ExpressionEnum::Member(exp, member) => {
+                let base = exp.type_check(table)?;
+                match exp.resolve_member(member, table) {
+                    // Members of top level constants are computed once,
+                    // under the path that names them.
+                    Some((constant, scope)) if scope.is_root() => {
+                        let path = self.to_string();
+                        scope.memoized_type(&path, self.span, || constant.infer_type(scope))
+                    }
+                    Some((constant, scope)) => constant.infer_type(scope),
+                    None => Err(ParserError::new_unknown_member(
+                        self.span,
+                        member.clone(),
+                        base.to_string(),
+                    )),
+                }
+            }
+            ExpressionEnum::Call(name, args) => {
+                let builtin = lookup_builtin(name, self.span)?;
+                builtin.check_arity(self.span, args.len())?;
+                let mut types = Vec::new();
+                for arg in args {
+                    types.push(arg.type_check(table)?);
+                }
+                builtin.type_check(self.span, &types)
+            }
+            ExpressionEnum::Una(UnaryExpressionKind::Neg, exp) => {
+                let operand = exp.type_check(table)?;
+                if !operand.is_numeric() {
+                    return Err(ParserError::new_type_mismatch(
+                        self.span,
+                        "numeric operand for `-`".to_owned(),
+                        operand.to_string(),
+                    ));
+                }
+                Ok(operand)
+            }
+            ExpressionEnum::Una(UnaryExpressionKind::Not, exp) => {
+                let operand = exp.type_check(table)?;
+                if operand != Type::Bool {
+                    return Err(ParserError::new_type_mismatch(
+                        self.span,
+                        "bool operand for `not`".to_owned(),
+                        operand.to_string(),
+                    ));
+                }
+                Ok(Type::Bool)
+            }
+            ExpressionEnum::Bin(kind, lhs, rhs) => {
+                let lhs_type = lhs.type_check(table)?;
+                let rhs_type = rhs.type_check(table)?;
+                let mismatch = |expected: &str| {
+                    return Err(ParserError::new_type_mismatch(
+                        self.span,
+                        format!("{} operands for `{}`", expected, kind.symbol()),
+                        format!("{} and {}", lhs_type, rhs_type),
+                    ));
+                };
+                match kind {
+                    BinaryExpressionKind::Add
+                    | BinaryExpressionKind::Sub
+                    | BinaryExpressionKind::Mult
+                    | BinaryExpressionKind::Div => {
+                        if !lhs_type.is_numeric() || !rhs_type.is_numeric() {
+                            return mismatch("numeric");
+                        }
+                        Ok(lhs_type.numeric_result(&rhs_type))
+                    }
+                    BinaryExpressionKind::And | BinaryExpressionKind::Or => {
+                        if lhs_type != Type::Bool || rhs_type != Type::Bool {
+                            return mismatch("bool");
+                        }
+                        Ok(Type::Bool)
+                    }
+                    BinaryExpressionKind::Less
+                    | BinaryExpressionKind::LessEqual
+                    | BinaryExpressionKind::Greater
+                    | BinaryExpressionKind::GreaterEqual => {
+                        if !lhs_type.is_numeric() || !rhs_type.is_numeric() {
+                            return mismatch("numeric");
+                        }
+                        Ok(Type::Bool)
+                    }
+                    BinaryExpressionKind::Equal | BinaryExpressionKind::Different => {
+                        if !lhs_type.is_comparable_with(&rhs_type) {
+                            return mismatch("comparable");
+                        }
+                        Ok(Type::Bool)
+                    }
+                    BinaryExpressionKind::In => match &rhs_type {
+                        Type::List(None) => Ok(Type::Bool),
+                        Type::List(Some(element)) if lhs_type.is_comparable_with(element) => {
+                            Ok(Type::Bool)
+                        }
+                        _ => mismatch("element and list"),
+                    },
+                }
+            }
+        };
+    }
+}
+
+fn identifier_value(name: &str, span: Span, table: &SymbolTable) -> Result<Value, ParserError> {
+    if let Some(value) = table.get_binding(name) {
+        return Ok(value.clone());
+    }
+    return match table.get(name) {
+        None => Err(ParserError::new_unresolved_name(span, name.to_owned())),
+        Some(Symbol::Pending(_)) => Err(ParserError::new_cyclic_definition(span, name.to_owned())),
+        Some(Symbol::Const(constant)) if table.is_global(name) => {
+            let root = table.root();
+            root.memoized_value(name, span, || constant.evaluate(root))
+        }
+        Some(Symbol::Const(constant)) => constant.evaluate(table),
+        Some(Symbol::Field(field)) if field.get_initializer().is_some() => {
+            field.get_initializer().unwrap().evaluate(table)
+        }
+        Some(_) => Err(ParserError::new_type_mismatch(
+            span,
+            "value".to_owned(),
+            Type::Selection.to_string(),
+        )),
+    };
+}
+
+impl Expression {
+    fn expect_bool(&self, table: &SymbolTable) -> Result<bool, ParserError> {
+        return match self.evaluate(table)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(ParserError::new_type_mismatch(
+                self.span,
+                Type::Bool.to_string(),
+                other.type_of().to_string(),
+            )),
+        };
+    }
+
+    pub fn evaluate(&self, table: &SymbolTable) -> Result<Value, ParserError> {
+        return match &self.content {
+            ExpressionEnum::Lit(Literal::Integer(i)) => Ok(Value::Integer(*i)),
+            ExpressionEnum::Lit(Literal::Float(v)) => Ok(Value::Float(*v)),
+            ExpressionEnum::Lit(Literal::Str(s)) => Ok(Value::Str(s.clone())),
+            ExpressionEnum::Lit(Literal::Indent(name)) => identifier_value(name, self.span, table),
+            ExpressionEnum::Lit(Literal::List(elements)) => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(element.evaluate(table)?);
+                }
+                Ok(Value::List(values))
+            }
+            ExpressionEnum::Member(exp, member) => {
+                // Checking the base first reports cycles through references
+                // before member resolution follows them.
+                exp.type_check(table)?;
+                match exp.resolve_member(member, table) {
+                    Some((constant, scope)) if scope.is_root() => {
+                        let path = self.to_string();
+                        return scope.memoized_value(&path, self.span, || constant.evaluate(scope));
+                    }
+                    Some((constant, scope)) => return constant.evaluate(scope),
+                    None => {}
+                }
+                let base = exp.evaluate(table)?;
+                match base.get_field(member) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(ParserError::new_unknown_member(
+                        self.span,
+                        member.clone(),
+                        base.type_of().to_string(),
+                    )),
+                }
+            }
+            ExpressionEnum::Call(name, args) => {
+                let builtin = lookup_builtin(name, self.span)?;
+                builtin.check_arity(self.span, args.len())?;
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(arg.evaluate(table)?);
+                }
+                builtin.evaluate(self.span, &values)
+            }
+            ExpressionEnum::Una(UnaryExpressionKind::Not, exp) => {
+                Ok(Value::Bool(!exp.expect_bool(table)?))
+            }
+            ExpressionEnum::Una(UnaryExpressionKind::Neg, exp) => {
+                let operand = exp.evaluate(table)?;
+                if !operand.is_numeric() {
+                    return Err(ParserError::new_type_mismatch(
+                        self.span,
+                        "numeric operand for `-`".to_owned(),
+                        operand.type_of().to_string(),
+                    ));
+                }
+                operand
+                    .neg()
+                    .ok_or_else(|| ParserError::new_arithmetic_overflow(self.span))
+            }
+            ExpressionEnum::Bin(BinaryExpressionKind::And, lhs, rhs) => Ok(Value::Bool(
+                lhs.expect_bool(table)? && rhs.expect_bool(table)?,
+            )),
+            ExpressionEnum::Bin(BinaryExpressionKind::Or, lhs, rhs) => Ok(Value::Bool(
+                lhs.expect_bool(table)? || rhs.expect_bool(table)?,
+            )),
+            ExpressionEnum::Bin(kind, lhs, rhs) => {
+                let lhs_value = lhs.evaluate(table)?;
+                let rhs_value = rhs.evaluate(table)?;
+                let mismatch = |expected: &str| {
+                    return ParserError::new_type_mismatch(
+                        self.span,
+                        format!("{} operands for `{}`", expected, kind.symbol()),
+                        format!("{} and {}", lhs_value.type_of(), rhs_value.type_of()),
+                    );
+                };
+                if matches!(
+                    kind,
+                    BinaryExpressionKind::Equal | BinaryExpressionKind::Different
+                ) {
+                    let equal = lhs_value
+                        .equals(&rhs_value)
+                        .ok_or_else(|| mismatch("comparable"))?;
+                    return Ok(Value::Bool(equal == (*kind == BinaryExpressionKind::Equal)));
+                }
+                if *kind == BinaryExpressionKind::In {
+                    let contained = rhs_value
+                        .contains(&lhs_value)
+                        .ok_or_else(|| mismatch("element and list"))?;
+                    return Ok(Value::Bool(contained));
+                }
+
+                if !lhs_value.is_numeric() || !rhs_value.is_numeric() {
+                    return Err(mismatch("numeric"));
+                }
+                let ordering = lhs_value.compare(&rhs_value);
+                let result = match kind {
+                    BinaryExpressionKind::Add => lhs_value.add(&rhs_value),
+                    BinaryExpressionKind::Sub => lhs_value.sub(&rhs_value),
+                    BinaryExpressionKind::Mult => lhs_value.mult(&rhs_value),
+                    BinaryExpressionKind::Div => {
+                        if rhs_value.as_float() == Some(0.0) {
+                            return Err(ParserError::new_division_by_zero(self.span));
+                        }
+                        lhs_value.div(&rhs_value)
+                    }
+                    BinaryExpressionKind::Less => ordering.map(|x| Value::Bool(x.is_lt())),
+                    BinaryExpressionKind::LessEqual => ordering.map(|x| Value::Bool(x.is_le())),
+                    BinaryExpressionKind::Greater => ordering.map(|x| Value::Bool(x.is_gt())),
+                    BinaryExpressionKind::GreaterEqual => ordering.map(|x| Value::Bool(x.is_ge())),
+                    _ => unreachable!(),
+                };
+                result.ok_or_else(|| ParserError::new_arithmetic_overflow(self.span))
+            }
+        };
+    }
+}
+
 impl Serializable for Expression {
     fn serialize(
         &self,
@@ -455,3 +1016,9 @@ impl Serializable for Expression {
         return self.content.serialize(f, ctx);
     }
 }
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return self.serialize(f, &SerializationContext::new());
+    }
+}
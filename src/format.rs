@@ -0,0 +1,424 @@
+use crate::alternative::*;
+use crate::choice::*;
+use crate::constant::*;
+use crate::declaration::*;
+use crate::document::Document;
+use crate::entry::*;
+use crate::error::*;
+use crate::expression::{Expression, Literal};
+use crate::lexer::Lexer;
+use crate::option::*;
+use crate::parser::Parser;
+use crate::requirement::*;
+use crate::token::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndentStyle {
+    Spaces,
+    Tabs,
+}
+
+// How the formatter lays out a document. Every nesting level is indented
+// by `indent_width` spaces, or by a tab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    pub indent_width: usize,
+    pub indent_style: IndentStyle,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        return FormatOptions::new();
+    }
+}
+
+impl FormatOptions {
+    pub fn new() -> FormatOptions {
+        return FormatOptions {
+            indent_width: 4,
+            indent_style: IndentStyle::Spaces,
+        };
+    }
+
+    fn indentation(&self, depth: usize) -> String {
+        return match self.indent_style {
+            IndentStyle::Spaces => " ".repeat(self.indent_width * depth),
+            IndentStyle::Tabs => "\t".repeat(depth),
+        };
+    }
+}
+
+enum EntryMember<'a> {
+    Const(&'a ConstantDeclaration),
+    Field(&'a EntryField),
+    Require(&'a RequirementDeclaration),
+}
+
+impl<'a> EntryMember<'a> {
+    fn span(&self) -> Span {
+        return match self {
+            EntryMember::Const(constant) => constant.span(),
+            EntryMember::Field(field) => field.span(),
+            EntryMember::Require(requirement) => requirement.span(),
+        };
+    }
+}
+
+// Lays out a document line by line. Comments and blank lines are not part
+// of the declarations, so they are placed by looking at the rows of the
+// source they came from: comments before a line stay before it, comments
+// at the end of a line stay at its end, and any run of blank lines between
+// two lines becomes a single blank line.
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+    source: Vec<&'a str>,
+    comments: Vec<(usize, String)>,
+    next_comment: usize,
+    last_row: Option<usize>,
+    output: String,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str, options: &'a FormatOptions) -> Formatter<'a> {
+        let lines: Vec<&str> = source.lines().collect();
+        let comments = Lexer::with_comments(source)
+            .filter(|x| x.kind == TokenKind::Comment)
+            .map(|x| {
+                let line = lines[x.span.lo.row];
+                let text: String = line.chars().skip(x.span.lo.column).collect();
+                (x.span.lo.row, text.trim_end().to_owned())
+            })
+            .collect();
+        return Formatter {
+            options,
+            source: lines,
+            comments,
+            next_comment: 0,
+            last_row: None,
+            output: String::new(),
+        };
+    }
+
+    fn separate(&mut self, row: usize) {
+        let last = match self.last_row {
+            Some(last) => last,
+            None => return,
+        };
+        let blank =
+            (last + 1..row).any(|x| self.source.get(x).is_some_and(|x| x.trim().is_empty()));
+        if blank {
+            self.output.push('\n');
+        }
+    }
+
+    fn comments_before(&mut self, depth: usize, row: usize) {
+        while let Some((comment_row, text)) = self.comments.get(self.next_comment).cloned() {
+            if comment_row >= row {
+                return;
+            }
+            self.separate(comment_row);
+            self.output.push_str(&self.options.indentation(depth));
+            self.output.push_str(&text);
+            self.output.push('\n');
+            self.last_row = Some(comment_row);
+            self.next_comment += 1;
+        }
+    }
+
+    // Emits a line produced by the source rows from `first` to `last`.
+    fn line(&mut self, depth: usize, text: &str, first: usize, last: usize) {
+        self.comments_before(depth, first);
+        self.separate(first);
+        self.output.push_str(&self.options.indentation(depth));
+        self.output.push_str(text);
+        while let Some((row, comment)) = self.comments.get(self.next_comment) {
+            if *row > last {
+                break;
+            }
+            self.output.push(' ');
+            self.output.push_str(comment);
+            self.next_comment += 1;
+        }
+        self.output.push('\n');
+        self.last_row = Some(last.max(self.last_row.unwrap_or(0)));
+    }
+
+    // Whether a comment sits on one of the rows of the span but its last,
+    // where it would be moved behind the end of the span.
+    fn has_comment_within(&self, span: Span) -> bool {
+        return self.comments[self.next_comment..]
+            .iter()
+            .any(|(row, _)| *row >= span.lo.row && *row < span.hi.row);
+    }
+
+    // A list with comments between its elements keeps one line per row of
+    // elements, so that every comment stays next to the elements it
+    // follows.
+    fn commented_list(
+        &mut self,
+        depth: usize,
+        text: String,
+        span: Span,
+        elements: &[Expression],
+        list: Span,
+    ) {
+        self.line(depth, &format!("{} = [", text), span.lo.row, list.lo.row);
+        let mut rows: Vec<(String, usize, usize)> = Vec::new();
+        for element in elements {
+            let element_span = element.span();
+            match rows.last_mut() {
+                Some((text, _, last)) if *last == element_span.lo.row => {
+                    text.push_str(&format!(" {},", element));
+                    *last = element_span.hi.row;
+                }
+                _ => rows.push((
+                    format!("{},", element),
+                    element_span.lo.row,
+                    element_span.hi.row,
+                )),
+            }
+        }
+        for (text, first, last) in rows {
+            self.line(depth + 1, &text, first, last);
+        }
+        self.comments_before(depth + 1, list.hi.row);
+        self.line(depth, "]", list.hi.row, list.hi.row);
+    }
+
+    fn finish(mut self) -> String {
+        self.comments_before(0, usize::MAX);
+        return self.output;
+    }
+
+    fn with_initializer(
+        &mut self,
+        depth: usize,
+        text: String,
+        span: Span,
+        initializer: Option<&Expression>,
+    ) {
+        return match initializer {
+            Some(initializer) if self.has_comment_within(initializer.span()) => {
+                match initializer.literal() {
+                    Some(Literal::List(elements)) => {
+                        self.commented_list(depth, text, span, elements, initializer.span())
+                    }
+                    _ => {
+                        let text = format!("{} = {}", text, initializer);
+                        self.line(depth, &text, span.lo.row, initializer.span().hi.row);
+                    }
+                }
+            }
+            Some(initializer) => {
+                let text = format!("{} = {}", text, initializer);
+                self.line(depth, &text, span.lo.row, initializer.span().hi.row);
+            }
+            None => self.line(depth, &text, span.lo.row, span.lo.row),
+        };
+    }
+
+    // Constants nested in another declaration are written without `const`.
+    fn constant(&mut self, constant: &ConstantDeclaration, depth: usize, keyword: bool) {
+        let text = match keyword {
+            true => format!("const {}", constant.name()),
+            false => constant.name().clone(),
+        };
+        self.with_initializer(depth, text, constant.span(), constant.get_initializer());
+        for field in constant.get_fields().into_iter().flatten() {
+            self.constant(field, depth + 1, false);
+        }
+    }
+
+    fn requirement(&mut self, requirement: &RequirementDeclaration, depth: usize) {
+        let span = requirement.span();
+        let condition = requirement.condition();
+        self.line(depth, "require", span.lo.row, span.lo.row);
+        self.line(
+            depth + 1,
+            &condition.to_string(),
+            condition.span().lo.row,
+            condition.span().hi.row,
+        );
+        let message = format!(
+            "\"{}\"",
+            crate::serialization::escape(requirement.message())
+        );
+        let row = requirement.message_span().lo.row;
+        self.line(depth + 1, &message, row, row);
+    }
+
+    // Options inside an alternative or another option are written without
+    // `opt`.
+    fn option(&mut self, option: &OptionDeclaration, depth: usize, keyword: bool) {
+        let text = match keyword {
+            true => format!("opt {}", option.name()),
+            false => option.name().clone(),
+        };
+        let row = option.span().lo.row;
+        self.line(depth, &text, row, row);
+        for field in option.get_fields() {
+            match field {
+                OptionField::SubOption(option) => self.option(option, depth + 1, false),
+                OptionField::Const(constant) => self.constant(constant, depth + 1, true),
+                OptionField::Alt(alternative) => self.alternative(alternative, depth + 1),
+                OptionField::Require(requirement) => self.requirement(requirement, depth + 1),
+            }
+        }
+    }
+
+    fn alternative(&mut self, alternative: &AlternativeDeclaration, depth: usize) {
+        let row = alternative.span().lo.row;
+        self.line(depth, &format!("alt {}", alternative.name()), row, row);
+        for option in alternative.get_fields() {
+            self.option(option, depth + 1, false);
+        }
+    }
+
+    fn entry_field(&mut self, field: &EntryField, depth: usize) {
+        let text = match field.min() == field.max() {
+            true => format!("{} {}", field.min(), field.name()),
+            false => format!("{}-{} {}", field.min(), field.max(), field.name()),
+        };
+        self.with_initializer(depth, text, field.span(), field.get_initializer());
+        for constant in field.get_fields() {
+            self.constant(constant, depth + 1, false);
+        }
+    }
+
+    // The entry keeps its constants, fields and requirements apart, they
+    // are written back in the order of the source so that comments stay
+    // next to the member they were written next to.
+    fn entry(&mut self, entry: &EntryDeclaration, depth: usize) {
+        let row = entry.span().lo.row;
+        self.line(depth, &format!("entry {}", entry.name()), row, row);
+        let constants = entry.get_constants().iter().map(EntryMember::Const);
+        let fields = entry.get_fields().iter().map(EntryMember::Field);
+        let requirements = entry.get_requirements().iter().map(EntryMember::Require);
+        let mut members: Vec<EntryMember> = constants.chain(fields).chain(requirements).collect();
+        members.sort_by_key(|x| {
+            let lo = x.span().lo;
+            (lo.row, lo.column)
+        });
+        for member in members {
+            match member {
+                EntryMember::Const(constant) => self.constant(constant, depth + 1, true),
+                EntryMember::Field(field) => self.entry_field(field, depth + 1),
+                EntryMember::Require(requirement) => self.requirement(requirement, depth + 1),
+            }
+        }
+    }
+
+    fn choice(&mut self, choice: &ChoiseDeclaration, depth: usize) {
+        let text = match choice.quantity() {
+            Some(quantity) => format!("{} {}", quantity, choice.symbol()),
+            None => choice.symbol().clone(),
+        };
+        self.with_initializer(depth, text, choice.span(), choice.get_value());
+        for field in choice.get_fields() {
+            self.choice(field, depth + 1);
+        }
+    }
+
+    fn declaration(&mut self, declaration: &Declaration) {
+        return match declaration {
+            Declaration::Opt(option) => self.option(option, 0, true),
+            Declaration::Const(constant) => self.constant(constant, 0, true),
+            Declaration::Alt(alternative) => self.alternative(alternative, 0),
+            Declaration::Choice(choice) => self.choice(choice, 0),
+            Declaration::Entry(entry) => self.entry(entry, 0),
+            Declaration::Require(requirement) => self.requirement(requirement, 0),
+        };
+    }
+}
+
+// Formats a document parsed from `source`, the source is needed to recover
+// the comments and blank lines the document does not keep.
+pub fn format_document(source: &str, document: &Document, options: &FormatOptions) -> String {
+    let mut formatter = Formatter::new(source, options);
+    for declaration in &document.entries {
+        formatter.declaration(declaration);
+    }
+    return formatter.finish();
+}
+
+pub fn format(source: &str, options: &FormatOptions) -> Result<String, ParserError> {
+    let document = Parser::parse(source)?;
+    return Ok(format_document(source, &document, options));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::*;
+
+    // The expectations are written with one space per level.
+    fn assert_formats(source: &str, expected: &str) {
+        let mut options = FormatOptions::new();
+        options.indent_width = 1;
+        let formatted = format(source, &options).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted, &options).unwrap(), formatted);
+    }
+
+    #[test]
+    fn keeps_comments_and_blank_lines() {
+        assert_formats(
+            "# profiles\nconst marine profile   # the basic one\n   WS = 4\n\n\n   # skill\n   BS =   (4)\n\nalt weapon\n   sword\n   # heavy\n   lascannon\n# end\n",
+            "# profiles\nconst marine profile # the basic one\n WS = 4\n\n # skill\n BS = 4\n\nalt weapon\n sword\n # heavy\n lascannon\n# end\n",
+        );
+    }
+
+    #[test]
+    fn minimal_parentheses() {
+        assert_formats(
+            "const a = ((1 + 2) * 3) - (4 - (5 - 6))\nconst b = not (a or b) and (c == d)\nconst c = -(a + 1) * [(1 + 2), (x.y)]\n",
+            "const a = (1 + 2) * 3 - (4 - (5 - 6))\nconst b = not (a or b) and c == d\nconst c = -(a + 1) * [1 + 2, x.y]\n",
+        );
+    }
+
+    #[test]
+    fn every_declaration() {
+        assert_formats(
+            "entry squad\n const cost = 10\n 5-10 marines = 3\n  profile = 4\n 1 sergent\n require\n  count(marines) <= 10 # bound\n  \"too many\"\nopt captain\n const cost = 5\n alt weapon\n  sword\n jump pack\n  shiny\n\n2 squad\n 5 marines\n heavy weapons = [\n  lascannon,\n ]\n",
+            "entry squad\n const cost = 10\n 5-10 marines = 3\n  profile = 4\n 1 sergent\n require\n  count(marines) <= 10 # bound\n  \"too many\"\nopt captain\n const cost = 5\n alt weapon\n  sword\n jump pack\n  shiny\n\n2 squad\n 5 marines\n heavy weapons = [lascannon]\n",
+        );
+    }
+
+    #[test]
+    fn comments_after_requirements() {
+        assert_formats(
+            "entry squad\n 5 marines\n require\n  count(marines) <= 10\n  \"too many\" # why\n\n# after\nconst a = 1\n",
+            "entry squad\n 5 marines\n require\n  count(marines) <= 10\n  \"too many\" # why\n\n# after\nconst a = 1\n",
+        );
+    }
+
+    #[test]
+    fn entry_members_keep_their_order() {
+        assert_formats(
+            "entry squad\n 5 marines\n require\n  count(marines) <= 10\n  \"too many\"\n # the leader\n 1 sergent\n const cost = 10\n",
+            "entry squad\n 5 marines\n require\n  count(marines) <= 10\n  \"too many\"\n # the leader\n 1 sergent\n const cost = 10\n",
+        );
+    }
+
+    #[test]
+    fn commented_lists_stay_multiline() {
+        assert_formats(
+            "entry squad\n 0-1 heavy weapons = [\n  lascannon, # big\n  bolter\n ]\nconst b = [\n 1, 2, # small\n # big\n 30\n] # end\nconst c = [\n 1,\n 2\n]\n",
+            "entry squad\n 0-1 heavy weapons = [\n  lascannon, # big\n  bolter,\n ]\nconst b = [\n 1, 2, # small\n # big\n 30,\n] # end\nconst c = [1, 2]\n",
+        );
+    }
+
+    #[test]
+    fn indentation_options() {
+        let source = "alt weapon\n sword\n  const cost = 5\n";
+        let mut options = FormatOptions::new();
+        assert_eq!(options.indent_width, 4);
+        let formatted = format(source, &options).unwrap();
+        assert_eq!(formatted, "alt weapon\n    sword\n        const cost = 5\n");
+        assert_eq!(format(&formatted, &options).unwrap(), formatted);
+
+        options.indent_style = IndentStyle::Tabs;
+        let formatted = format(source, &options).unwrap();
+        assert_eq!(formatted, "alt weapon\n\tsword\n\t\tconst cost = 5\n");
+        assert_eq!(format(&formatted, &options).unwrap(), formatted);
+    }
+}
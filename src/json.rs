@@ -0,0 +1,1202 @@
+use crate::alternative::*;
+use crate::choice::*;
+use crate::constant::*;
+use crate::declaration::*;
+use crate::document::Document;
+use crate::entry::*;
+use crate::error::*;
+use crate::expression::*;
+use crate::lexer::Lexer;
+use crate::option::*;
+use crate::requirement::*;
+use crate::token::*;
+use std::fmt::Display;
+
+// A JSON value. Integers are kept apart from floats so that they round
+// trip without losing precision.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+fn write_string(f: &mut std::fmt::Formatter, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    return write!(f, "\"");
+}
+
+// Compact JSON, without any whitespace.
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Integer(i) => write!(f, "{}", i),
+            // JSON has no infinities, `1e999` is exported as null.
+            Json::Float(v) if !v.is_finite() => write!(f, "null"),
+            Json::Float(v) => write!(f, "{:?}", v),
+            Json::Str(s) => write_string(f, s),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        };
+    }
+}
+
+impl Json {
+    pub fn str(s: &str) -> Json {
+        return Json::Str(s.to_owned());
+    }
+
+    pub fn object(members: Vec<(&str, Json)>) -> Json {
+        let members = members.into_iter().map(|(k, v)| (k.to_owned(), v));
+        return Json::Object(members.collect());
+    }
+
+    pub fn array<T: ToJson>(elements: &[T]) -> Json {
+        return Json::Array(elements.iter().map(|x| x.to_json()).collect());
+    }
+
+    pub fn optional<T: ToJson>(value: Option<&T>) -> Json {
+        return value.map_or(Json::Null, |x| x.to_json());
+    }
+
+    // The member of an object with the given key, null members included.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        return match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        };
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        return match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        };
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        return match self {
+            Json::Integer(i) => Some(*i),
+            _ => None,
+        };
+    }
+}
+
+// The JSON shape of the parsed documents. Every declaration and expression
+// is an object with a `kind` and a `span`:
+//
+// document      {"declarations": [declaration]}
+// span          {"lo": location, "hi": location}
+// location      {"row": int, "column": int}, both counted from zero
+//
+// const         {"kind": "const", "name", "body": body}
+// body          {"kind": "direct", "value": expression}
+//               {"kind": "content", "constants": [const]}
+// opt           {"kind": "opt", "name", "fields": [opt | const | alt | require]}
+// alt           {"kind": "alt", "name", "options": [opt]}
+// entry         {"kind": "entry", "name", "constants": [const],
+//                "fields": [field], "requirements": [require]}
+// field         {"kind": "field", "name", "min", "max",
+//                "initializer": expression | null, "constants": [const]}
+// require       {"kind": "require", "condition": expression, "message",
+//                "message_span": span}
+// choice        {"kind": "choice", "quantity": int | null, "name",
+//                "value": expression | null, "choices": [choice]}
+//
+// expression    {"kind": "integer" | "float" | "string", "value"}
+//               {"kind": "identifier", "name"}
+//               {"kind": "list", "elements": [expression]}
+//               {"kind": "unary", "operator": "-" | "not", "operand"}
+//               {"kind": "binary", "operator", "lhs", "rhs"}, where the
+//                operator is written as in the source, such as "<="
+//               {"kind": "member", "base", "member"}
+//               {"kind": "call", "name", "arguments": [expression]}
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+fn location(location: &SourceLocation) -> Json {
+    return Json::object(vec![
+        ("row", Json::Integer(location.row as i64)),
+        ("column", Json::Integer(location.column as i64)),
+    ]);
+}
+
+impl ToJson for Span {
+    fn to_json(&self) -> Json {
+        return Json::object(vec![("lo", location(&self.lo)), ("hi", location(&self.hi))]);
+    }
+}
+
+fn node(kind: &str, mut members: Vec<(&str, Json)>, span: Span) -> Json {
+    members.insert(0, ("kind", Json::str(kind)));
+    members.push(("span", span.to_json()));
+    return Json::object(members);
+}
+
+impl ToJson for Expression {
+    fn to_json(&self) -> Json {
+        let span = self.span();
+        return match self.content() {
+            ExpressionEnum::Lit(Literal::Integer(i)) => {
+                node("integer", vec![("value", Json::Integer(*i))], span)
+            }
+            ExpressionEnum::Lit(Literal::Float(v)) => {
+                node("float", vec![("value", Json::Float(*v))], span)
+            }
+            ExpressionEnum::Lit(Literal::Str(s)) => {
+                node("string", vec![("value", Json::str(s))], span)
+            }
+            ExpressionEnum::Lit(Literal::Indent(name)) => {
+                node("identifier", vec![("name", Json::str(name))], span)
+            }
+            ExpressionEnum::Lit(Literal::List(elements)) => {
+                node("list", vec![("elements", Json::array(elements))], span)
+            }
+            ExpressionEnum::Una(kind, operand) => {
+                let operator = match kind {
+                    UnaryExpressionKind::Neg => "-",
+                    UnaryExpressionKind::Not => "not",
+                };
+                let members = vec![
+                    ("operator", Json::str(operator)),
+                    ("operand", operand.to_json()),
+                ];
+                node("unary", members, span)
+            }
+            ExpressionEnum::Bin(kind, lhs, rhs) => {
+                let members = vec![
+                    ("operator", Json::str(kind.symbol())),
+                    ("lhs", lhs.to_json()),
+                    ("rhs", rhs.to_json()),
+                ];
+                node("binary", members, span)
+            }
+            ExpressionEnum::Member(base, member) => {
+                let members = vec![("base", base.to_json()), ("member", Json::str(member))];
+                node("member", members, span)
+            }
+            ExpressionEnum::Call(name, args) => {
+                let members = vec![("name", Json::str(name)), ("arguments", Json::array(args))];
+                node("call", members, span)
+            }
+        };
+    }
+}
+
+impl ToJson for ConstantBody {
+    fn to_json(&self) -> Json {
+        return match self {
+            ConstantBody::Direct(value) => Json::object(vec![
+                ("kind", Json::str("direct")),
+                ("value", value.to_json()),
+            ]),
+            ConstantBody::Content(constants) => Json::object(vec![
+                ("kind", Json::str("content")),
+                ("constants", Json::array(constants)),
+            ]),
+        };
+    }
+}
+
+impl ToJson for ConstantDeclaration {
+    fn to_json(&self) -> Json {
+        let members = vec![
+            ("name", Json::str(self.name())),
+            ("body", self.body().to_json()),
+        ];
+        return node("const", members, self.span());
+    }
+}
+
+impl ToJson for RequirementDeclaration {
+    fn to_json(&self) -> Json {
+        let members = vec![
+            ("condition", self.condition().to_json()),
+            ("message", Json::str(self.message())),
+            ("message_span", self.message_span().to_json()),
+        ];
+        return node("require", members, self.span());
+    }
+}
+
+impl ToJson for OptionField {
+    fn to_json(&self) -> Json {
+        return match self {
+            OptionField::SubOption(option) => option.to_json(),
+            OptionField::Const(constant) => constant.to_json(),
+            OptionField::Alt(alternative) => alternative.to_json(),
+            OptionField::Require(requirement) => requirement.to_json(),
+        };
+    }
+}
+
+impl ToJson for OptionDeclaration {
+    fn to_json(&self) -> Json {
+        let members = vec![
+            ("name", Json::str(self.name())),
+            ("fields", Json::array(self.get_fields())),
+        ];
+        return node("opt", members, self.span());
+    }
+}
+
+impl ToJson for AlternativeDeclaration {
+    fn to_json(&self) -> Json {
+        let members = vec![
+            ("name", Json::str(self.name())),
+            ("options", Json::array(self.get_fields())),
+        ];
+        return node("alt", members, self.span());
+    }
+}
+
+impl ToJson for EntryField {
+    fn to_json(&self) -> Json {
+        let members = vec![
+            ("name", Json::str(self.name())),
+            ("min", Json::Integer(self.min())),
+            ("max", Json::Integer(self.max())),
+            ("initializer", Json::optional(self.get_initializer())),
+            ("constants", Json::array(self.get_fields())),
+        ];
+        return node("field", members, self.span());
+    }
+}
+
+impl ToJson for EntryDeclaration {
+    fn to_json(&self) -> Json {
+        let members = vec![
+            ("name", Json::str(self.name())),
+            ("constants", Json::array(self.get_constants())),
+            ("fields", Json::array(self.get_fields())),
+            ("requirements", Json::array(self.get_requirements())),
+        ];
+        return node("entry", members, self.span());
+    }
+}
+
+impl ToJson for ChoiseDeclaration {
+    fn to_json(&self) -> Json {
+        let members = vec![
+            (
+                "quantity",
+                self.quantity().map_or(Json::Null, Json::Integer),
+            ),
+            ("name", Json::str(self.symbol())),
+            ("value", Json::optional(self.get_value())),
+            ("choices", Json::array(self.get_fields())),
+        ];
+        return node("choice", members, self.span());
+    }
+}
+
+impl ToJson for Declaration {
+    fn to_json(&self) -> Json {
+        return match self {
+            Declaration::Opt(option) => option.to_json(),
+            Declaration::Const(constant) => constant.to_json(),
+            Declaration::Alt(alternative) => alternative.to_json(),
+            Declaration::Choice(choice) => choice.to_json(),
+            Declaration::Entry(entry) => entry.to_json(),
+            Declaration::Require(requirement) => requirement.to_json(),
+        };
+    }
+}
+
+impl ToJson for Document {
+    fn to_json(&self) -> Json {
+        return Json::object(vec![("declarations", Json::array(&self.entries))]);
+    }
+}
+
+// Reads JSON text, errors point at the row and column of the text.
+struct JsonParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    location: SourceLocation,
+}
+
+impl<'a> JsonParser<'a> {
+    fn peek(&mut self) -> Option<char> {
+        return self.chars.peek().copied();
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.location.row += 1;
+            self.location.column = 0;
+        } else {
+            self.location.column += 1;
+        }
+        return Some(c);
+    }
+
+    fn error(&self, message: String) -> ParserError {
+        let span = Span {
+            lo: self.location,
+            hi: self.location,
+        };
+        return ParserError::new_invalid_json(span, message);
+    }
+
+    fn found(&mut self) -> String {
+        return match self.peek() {
+            Some(c) => format!("`{}`", c),
+            None => "end of input".to_owned(),
+        };
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParserError> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            let found = self.found();
+            return Err(self.error(format!("expected `{}`, found {}", expected, found)));
+        }
+        self.bump();
+        return Ok(());
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, ParserError> {
+        for expected in keyword.chars() {
+            if self.peek() != Some(expected) {
+                let found = self.found();
+                return Err(self.error(format!("expected `{}`, found {}", keyword, found)));
+            }
+            self.bump();
+        }
+        return Ok(value);
+    }
+
+    fn value(&mut self) -> Result<Json, ParserError> {
+        self.skip_whitespace();
+        return match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::Str(self.string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => {
+                let found = self.found();
+                Err(self.error(format!("expected a value, found {}", found)))
+            }
+        };
+    }
+
+    // Calls `element` for every element of a `[...]` or `{...}` sequence.
+    fn sequence(
+        &mut self,
+        close: char,
+        mut element: impl FnMut(&mut Self) -> Result<(), ParserError>,
+    ) -> Result<(), ParserError> {
+        self.bump();
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.bump();
+            return Ok(());
+        }
+        loop {
+            element(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(c) if c == close => {
+                    self.bump();
+                    return Ok(());
+                }
+                _ => {
+                    let found = self.found();
+                    return Err(self.error(format!("expected `,` or `{}`, found {}", close, found)));
+                }
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, ParserError> {
+        let mut elements = Vec::new();
+        self.sequence(']', |parser| {
+            elements.push(parser.value()?);
+            return Ok(());
+        })?;
+        return Ok(Json::Array(elements));
+    }
+
+    fn object(&mut self) -> Result<Json, ParserError> {
+        let mut members = Vec::new();
+        self.sequence('}', |parser| {
+            parser.skip_whitespace();
+            if parser.peek() != Some('"') {
+                let found = parser.found();
+                return Err(parser.error(format!("expected a key, found {}", found)));
+            }
+            let key = parser.string()?;
+            parser.expect(':')?;
+            members.push((key, parser.value()?));
+            return Ok(());
+        })?;
+        return Ok(Json::Object(members));
+    }
+
+    fn hex_escape(&mut self) -> Result<u32, ParserError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.peek().and_then(|x| x.to_digit(16));
+            match digit {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("invalid `\\u` escape".to_owned())),
+            }
+            self.bump();
+        }
+        return Ok(code);
+    }
+
+    fn string(&mut self) -> Result<String, ParserError> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            let c = match self.bump() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string".to_owned())),
+            };
+            match c {
+                '"' => return Ok(s),
+                '\\' => {}
+                c if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string".to_owned()));
+                }
+                c => {
+                    s.push(c);
+                    continue;
+                }
+            }
+            let escaped = match self.bump() {
+                Some('"') => '"',
+                Some('\\') => '\\',
+                Some('/') => '/',
+                Some('b') => '\u{8}',
+                Some('f') => '\u{c}',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('u') => {
+                    let mut code = self.hex_escape()?;
+                    // Characters outside of the basic plane are written as
+                    // a pair of surrogates.
+                    if (0xD800..0xDC00).contains(&code) && self.peek() == Some('\\') {
+                        self.bump();
+                        self.keyword("u", Json::Null)?;
+                        let low = self.hex_escape()?;
+                        code =
+                            0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                    }
+                    match char::from_u32(code) {
+                        Some(c) => c,
+                        None => return Err(self.error("invalid `\\u` escape".to_owned())),
+                    }
+                }
+                _ => return Err(self.error("invalid escape".to_owned())),
+            };
+            s.push(escaped);
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, ParserError> {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+                break;
+            }
+            text.push(c);
+            self.bump();
+        }
+        let is_float = text.contains(['.', 'e', 'E']);
+        if !is_float {
+            if let Ok(i) = text.parse() {
+                return Ok(Json::Integer(i));
+            }
+        }
+        return match text.parse() {
+            Ok(v) => Ok(Json::Float(v)),
+            Err(_) => Err(self.error(format!("invalid number `{}`", text))),
+        };
+    }
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, ParserError> {
+        let mut parser = JsonParser {
+            chars: text.chars().peekable(),
+            location: SourceLocation { row: 0, column: 0 },
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.peek().is_some() {
+            let found = parser.found();
+            return Err(parser.error(format!("expected end of input, found {}", found)));
+        }
+        return Ok(value);
+    }
+
+    // The same value without any span member, such as `span` or
+    // `message_span`. Two documents that only differ by their spans have
+    // equal JSON once their spans are removed.
+    pub fn without_spans(&self) -> Json {
+        return match self {
+            Json::Array(elements) => {
+                Json::Array(elements.iter().map(|x| x.without_spans()).collect())
+            }
+            Json::Object(members) => Json::Object(
+                members
+                    .iter()
+                    .filter(|(key, _)| key != "span" && !key.ends_with("_span"))
+                    .map(|(key, value)| (key.clone(), value.without_spans()))
+                    .collect(),
+            ),
+            other => other.clone(),
+        };
+    }
+}
+
+// Builds a value from the shape described above. Spans are optional, and
+// so are lists that are empty and members that are null. Anything ODL
+// cannot express is rejected, such as names that are not identifiers or
+// negative literals, so that every document read this way can be written
+// as ODL and parsed back into the same declarations, spans aside.
+pub trait FromJson: Sized {
+    fn from_json(json: &Json, path: &str) -> Result<Self, ParserError>;
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        return key.to_owned();
+    }
+    return format!("{}.{}", path, key);
+}
+
+// Names are made of identifiers separated by single spaces, such as
+// `tactical marines`, since that is how they are written back.
+fn is_name(name: &str) -> bool {
+    let mut lexer = Lexer::new(name);
+    let mut expect_word = true;
+    while let Some((token, text)) = lexer.next_with_text() {
+        let valid = match expect_word {
+            true => matches!(token.kind, TokenKind::Ident(_)),
+            false => text == " ",
+        };
+        if !valid {
+            return false;
+        }
+        expect_word = !expect_word;
+    }
+    return !expect_word;
+}
+
+// The members of an object, with the path used in the errors.
+struct Members<'a> {
+    members: &'a [(String, Json)],
+    path: &'a str,
+}
+
+impl<'a> Members<'a> {
+    fn new(json: &'a Json, path: &'a str) -> Result<Members<'a>, ParserError> {
+        return match json {
+            Json::Object(members) => Ok(Members { members, path }),
+            _ => Err(ParserError::new_invalid_json_document(
+                path.to_owned(),
+                "expected an object".to_owned(),
+            )),
+        };
+    }
+
+    fn error(&self, key: &str, message: &str) -> ParserError {
+        return ParserError::new_invalid_json_document(
+            child_path(self.path, key),
+            message.to_owned(),
+        );
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Json> {
+        return match self.members.iter().find(|(k, _)| k == key) {
+            Some((_, Json::Null)) | None => None,
+            Some((_, value)) => Some(value),
+        };
+    }
+
+    fn required(&self, key: &str) -> Result<&'a Json, ParserError> {
+        return self
+            .get(key)
+            .ok_or_else(|| self.error(key, "missing member"));
+    }
+
+    fn string(&self, key: &str) -> Result<String, ParserError> {
+        return match self.required(key)? {
+            Json::Str(s) => Ok(s.clone()),
+            _ => Err(self.error(key, "expected a string")),
+        };
+    }
+
+    fn name(&self, key: &str) -> Result<String, ParserError> {
+        let name = self.string(key)?;
+        if !is_name(&name) {
+            let message = format!("`{}` is not a valid name", name);
+            return Err(self.error(key, &message));
+        }
+        return Ok(name);
+    }
+
+    fn integer(&self, key: &str) -> Result<i64, ParserError> {
+        return match self.required(key)? {
+            Json::Integer(i) => Ok(*i),
+            _ => Err(self.error(key, "expected an integer")),
+        };
+    }
+
+    fn count(&self, key: &str) -> Result<i64, ParserError> {
+        let count = self.integer(key)?;
+        if count < 0 {
+            return Err(self.error(key, "expected a non negative integer"));
+        }
+        return Ok(count);
+    }
+
+    fn value<T: FromJson>(&self, key: &str) -> Result<T, ParserError> {
+        return T::from_json(self.required(key)?, &child_path(self.path, key));
+    }
+
+    fn optional<T: FromJson>(&self, key: &str) -> Result<Option<T>, ParserError> {
+        return match self.get(key) {
+            Some(json) => Ok(Some(T::from_json(json, &child_path(self.path, key))?)),
+            None => Ok(None),
+        };
+    }
+
+    fn list<T: FromJson>(&self, key: &str) -> Result<Vec<T>, ParserError> {
+        let elements = match self.get(key) {
+            Some(Json::Array(elements)) => elements,
+            Some(_) => return Err(self.error(key, "expected an array")),
+            None => return Ok(Vec::new()),
+        };
+        let path = child_path(self.path, key);
+        return elements
+            .iter()
+            .enumerate()
+            .map(|(i, x)| T::from_json(x, &format!("{}[{}]", path, i)))
+            .collect();
+    }
+
+    fn span(&self) -> Result<Span, ParserError> {
+        return Ok(self.optional("span")?.unwrap_or_default());
+    }
+
+    fn kind(&self) -> Result<String, ParserError> {
+        return self.string("kind");
+    }
+
+    // Members whose kind is implied by where they are may omit it.
+    fn expect_kind(&self, expected: &str) -> Result<(), ParserError> {
+        if self.get("kind").is_some() && self.kind()? != expected {
+            return Err(self.error("kind", &format!("expected `{}`", expected)));
+        }
+        return Ok(());
+    }
+
+    fn unknown_kind(&self) -> ParserError {
+        let kind = self.kind().unwrap_or_default();
+        return self.error("kind", &format!("unknown kind `{}`", kind));
+    }
+}
+
+impl FromJson for SourceLocation {
+    fn from_json(json: &Json, path: &str) -> Result<SourceLocation, ParserError> {
+        let members = Members::new(json, path)?;
+        return Ok(SourceLocation {
+            row: members.count("row")? as usize,
+            column: members.count("column")? as usize,
+        });
+    }
+}
+
+impl FromJson for Span {
+    fn from_json(json: &Json, path: &str) -> Result<Span, ParserError> {
+        let members = Members::new(json, path)?;
+        return Ok(Span {
+            lo: members.value("lo")?,
+            hi: members.value("hi")?,
+        });
+    }
+}
+
+impl FromJson for Expression {
+    fn from_json(json: &Json, path: &str) -> Result<Expression, ParserError> {
+        let members = Members::new(json, path)?;
+        let span = members.span()?;
+        let kind = members.kind()?;
+        // The parser never produces negative literals, `-1` is a negation.
+        let negative = || {
+            members.error(
+                "value",
+                "expected a non negative number, use a `-` unary expression",
+            )
+        };
+        let expression = match kind.as_str() {
+            "integer" => Expression::int(members.count("value").map_err(|_| negative())?, span),
+            "float" => {
+                let value = match members.required("value")? {
+                    Json::Float(v) => *v,
+                    Json::Integer(i) => *i as f64,
+                    _ => return Err(members.error("value", "expected a number")),
+                };
+                if !value.is_finite() || value.is_sign_negative() {
+                    return Err(negative());
+                }
+                Expression::float(value, span)
+            }
+            "string" => Expression::str(members.string("value")?, span),
+            "identifier" => Expression::ident(members.name("name")?, span),
+            "list" => Expression::list(members.list("elements")?, span),
+            "unary" => {
+                let kind = match members.string("operator")?.as_str() {
+                    "-" => UnaryExpressionKind::Neg,
+                    "not" => UnaryExpressionKind::Not,
+                    _ => return Err(members.error("operator", "expected `-` or `not`")),
+                };
+                Expression::new_una(kind, members.value("operand")?, span)
+            }
+            "binary" => {
+                let operator = members.string("operator")?;
+                let kind = match BinaryExpressionKind::from_symbol(&operator) {
+                    Some(kind) => kind,
+                    None => {
+                        let message = format!("unknown operator `{}`", operator);
+                        return Err(members.error("operator", &message));
+                    }
+                };
+                Expression::new_bin(kind, members.value("lhs")?, members.value("rhs")?, span)
+            }
+            "member" => Expression::member(members.value("base")?, members.name("member")?, span),
+            "call" => Expression::call(members.name("name")?, members.list("arguments")?, span),
+            _ => return Err(members.unknown_kind()),
+        };
+        return Ok(expression);
+    }
+}
+
+impl FromJson for ConstantDeclaration {
+    fn from_json(json: &Json, path: &str) -> Result<ConstantDeclaration, ParserError> {
+        let members = Members::new(json, path)?;
+        members.expect_kind("const")?;
+        let name = members.name("name")?;
+        let span = members.span()?;
+        let body_path = child_path(path, "body");
+        let body = Members::new(members.required("body")?, &body_path)?;
+        return match body.kind()?.as_str() {
+            "direct" => Ok(ConstantDeclaration::new_direct(
+                name,
+                body.value("value")?,
+                span,
+            )),
+            // ODL has no way to write a constant without any content.
+            "content" => {
+                let constants = body.list("constants")?;
+                if constants.is_empty() {
+                    return Err(body.error("constants", "expected at least one constant"));
+                }
+                let mut constant = ConstantDeclaration::new(name, span);
+                *constant.get_fields_mut().unwrap() = constants;
+                Ok(constant)
+            }
+            _ => Err(body.unknown_kind()),
+        };
+    }
+}
+
+impl FromJson for RequirementDeclaration {
+    fn from_json(json: &Json, path: &str) -> Result<RequirementDeclaration, ParserError> {
+        let members = Members::new(json, path)?;
+        members.expect_kind("require")?;
+        let mut requirement = RequirementDeclaration::new(
+            members.value("condition")?,
+            members.string("message")?,
+            members.span()?,
+        );
+        if let Some(span) = members.optional("message_span")? {
+            requirement.set_message_span(span);
+        }
+        return Ok(requirement);
+    }
+}
+
+impl FromJson for OptionField {
+    fn from_json(json: &Json, path: &str) -> Result<OptionField, ParserError> {
+        let members = Members::new(json, path)?;
+        return match members.kind()?.as_str() {
+            "opt" => Ok(OptionField::SubOption(OptionDeclaration::from_json(
+                json, path,
+            )?)),
+            "const" => Ok(OptionField::Const(ConstantDeclaration::from_json(
+                json, path,
+            )?)),
+            "alt" => Ok(OptionField::Alt(AlternativeDeclaration::from_json(
+                json, path,
+            )?)),
+            "require" => Ok(OptionField::Require(RequirementDeclaration::from_json(
+                json, path,
+            )?)),
+            _ => Err(members.unknown_kind()),
+        };
+    }
+}
+
+impl FromJson for OptionDeclaration {
+    fn from_json(json: &Json, path: &str) -> Result<OptionDeclaration, ParserError> {
+        let members = Members::new(json, path)?;
+        members.expect_kind("opt")?;
+        let mut option = OptionDeclaration::new(members.name("name")?, members.span()?);
+        *option.get_fields_mut() = members.list("fields")?;
+        return Ok(option);
+    }
+}
+
+impl FromJson for AlternativeDeclaration {
+    fn from_json(json: &Json, path: &str) -> Result<AlternativeDeclaration, ParserError> {
+        let members = Members::new(json, path)?;
+        members.expect_kind("alt")?;
+        let mut alternative = AlternativeDeclaration::new(members.name("name")?, members.span()?);
+        *alternative.get_fields_mut() = members.list("options")?;
+        return Ok(alternative);
+    }
+}
+
+impl FromJson for EntryField {
+    fn from_json(json: &Json, path: &str) -> Result<EntryField, ParserError> {
+        let members = Members::new(json, path)?;
+        members.expect_kind("field")?;
+        let min = members.count("min")?;
+        let max = members.count("max")?;
+        if max < min {
+            return Err(members.error("max", "expected at least `min`"));
+        }
+        let mut field = EntryField::new(
+            members.name("name")?,
+            min,
+            max,
+            members.optional("initializer")?,
+            members.span()?,
+        );
+        *field.get_fields_mut() = members.list("constants")?;
+        return Ok(field);
+    }
+}
+
+impl FromJson for EntryDeclaration {
+    fn from_json(json: &Json, path: &str) -> Result<EntryDeclaration, ParserError> {
+        let members = Members::new(json, path)?;
+        members.expect_kind("entry")?;
+        let mut entry = EntryDeclaration::new(members.name("name")?, members.span()?);
+        *entry.get_constants_mut() = members.list("constants")?;
+        *entry.get_fields_mut() = members.list("fields")?;
+        *entry.get_requirements_mut() = members.list("requirements")?;
+        return Ok(entry);
+    }
+}
+
+impl FromJson for ChoiseDeclaration {
+    fn from_json(json: &Json, path: &str) -> Result<ChoiseDeclaration, ParserError> {
+        let members = Members::new(json, path)?;
+        members.expect_kind("choice")?;
+        let quantity = match members.get("quantity") {
+            Some(_) => Some(members.count("quantity")?),
+            None => None,
+        };
+        let mut choice = ChoiseDeclaration::new(
+            members.name("name")?,
+            members.optional("value")?,
+            members.span()?,
+        );
+        choice.set_quantity(quantity);
+        *choice.get_fields_mut() = members.list("choices")?;
+        return Ok(choice);
+    }
+}
+
+impl FromJson for Declaration {
+    fn from_json(json: &Json, path: &str) -> Result<Declaration, ParserError> {
+        let members = Members::new(json, path)?;
+        return match members.kind()?.as_str() {
+            "opt" => Ok(Declaration::Opt(OptionDeclaration::from_json(json, path)?)),
+            "const" => Ok(Declaration::Const(ConstantDeclaration::from_json(
+                json, path,
+            )?)),
+            "alt" => Ok(Declaration::Alt(AlternativeDeclaration::from_json(
+                json, path,
+            )?)),
+            "choice" => Ok(Declaration::Choice(ChoiseDeclaration::from_json(
+                json, path,
+            )?)),
+            "entry" => Ok(Declaration::Entry(EntryDeclaration::from_json(json, path)?)),
+            "require" => Ok(Declaration::Require(RequirementDeclaration::from_json(
+                json, path,
+            )?)),
+            _ => Err(members.unknown_kind()),
+        };
+    }
+}
+
+impl FromJson for Document {
+    fn from_json(json: &Json, path: &str) -> Result<Document, ParserError> {
+        let members = Members::new(json, path)?;
+        let mut document = Document::new();
+        document.entries = members.list("declarations")?;
+        return Ok(document);
+    }
+}
+
+// Reads a document from JSON text, such as the output of
+// `odl-check check --emit json`.
+pub fn document_from_json(text: &str) -> Result<Document, ParserError> {
+    return Document::from_json(&Json::parse(text)?, "");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn json_values() {
+        let value = Json::object(vec![
+            ("text", Json::str("a \"b\"\n\u{1}")),
+            (
+                "list",
+                Json::Array(vec![Json::Integer(-1), Json::Float(2.0), Json::Null]),
+            ),
+            (
+                "flags",
+                Json::Array(vec![Json::Bool(true), Json::Float(f64::INFINITY)]),
+            ),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            "{\"text\":\"a \\\"b\\\"\\n\\u0001\",\"list\":[-1,2.0,null],\"flags\":[true,null]}"
+        );
+    }
+
+    #[test]
+    fn expression_json() {
+        let expression = Parser::parse_expression("-a.b + f(1.5, \"s\", [])").unwrap();
+        let json = expression.to_json().to_string();
+        let span = |lo: usize, hi: usize| {
+            format!(
+                "\"span\":{{\"lo\":{{\"row\":0,\"column\":{}}},\"hi\":{{\"row\":0,\"column\":{}}}}}",
+                lo, hi
+            )
+        };
+        assert_eq!(
+            json,
+            format!(
+                "{{\"kind\":\"binary\",\"operator\":\"+\",\"lhs\":{{\"kind\":\"unary\",\"operator\":\"-\",\"operand\":{{\"kind\":\"member\",\"base\":{{\"kind\":\"identifier\",\"name\":\"a\",{}}},\"member\":\"b\",{}}},{}}},\"rhs\":{{\"kind\":\"call\",\"name\":\"f\",\"arguments\":[{{\"kind\":\"float\",\"value\":1.5,{}}},{{\"kind\":\"string\",\"value\":\"s\",{}}},{{\"kind\":\"list\",\"elements\":[],{}}}],{}}},{}}}",
+                span(1, 2),
+                span(1, 4),
+                span(0, 4),
+                span(9, 12),
+                span(14, 17),
+                span(19, 21),
+                span(7, 22),
+                span(0, 22)
+            )
+        );
+    }
+
+    #[test]
+    fn document_json() {
+        let document = Parser::parse("entry squad\n 5-10 marines\n\nsquad\n 5 marines\n").unwrap();
+        let json = document.to_json();
+        let declarations = match &json {
+            Json::Object(members) => &members[0].1,
+            _ => panic!("expected an object"),
+        };
+        let kinds: Vec<String> = match declarations {
+            Json::Array(elements) => elements
+                .iter()
+                .map(|x| match x {
+                    Json::Object(members) => members[0].1.to_string(),
+                    _ => panic!("expected an object"),
+                })
+                .collect(),
+            _ => panic!("expected an array"),
+        };
+        assert_eq!(kinds, vec!["\"entry\"", "\"choice\""]);
+        let text = json.to_string();
+        assert!(text.contains("{\"kind\":\"field\",\"name\":\"marines\",\"min\":5,\"max\":10,\"initializer\":null,\"constants\":[]"));
+        assert!(text.contains("{\"kind\":\"choice\",\"quantity\":5,\"name\":\"marines\",\"value\":null,\"choices\":[]"));
+    }
+
+    const CODEX: &str = "const marine profile\n WS = 4\n stats\n  BS = 3.5\n\nopt captain\n const cost = 80\n alt weapon\n  power sword\n  power fist\n   const cost = 10\n jump pack\n require\n  not (count(weapon) > 1 or false) and -1 < 2\n  \"one \\\"weapon\\\"\\n only\"\n\nalt heavy weapon\n heavy bolter\n lascannon\n\nentry tactical squad\n const cost per model = 14\n 5-10 tactical marines = 1 - (2 - 3)\n  profile = marine profile.stats.BS\n 0-1 heavy weapons = [heavy bolter, \"x\", [], 2 * (3 + 4)]\n require\n  max(1, 2.5) >= len([1]) and a in [a]\n  \"rules\"\n\nrequire\n count(captain) <= 1\n \"at most one captain\"\n\n2 tactical squad\n 5 tactical marines\n heavy weapons = [lascannon]\n";
+
+    fn without_spans(document: &Document) -> Json {
+        return document.to_json().without_spans();
+    }
+
+    // JSON -> Document -> ODL -> Document must give back the same
+    // declarations, spans aside.
+    fn assert_round_trips(document: &Document) {
+        let reparsed = Parser::parse(&document.to_string()).unwrap();
+        assert_eq!(without_spans(&reparsed), without_spans(document));
+    }
+
+    #[test]
+    fn json_parsing() {
+        let json = Json::parse(
+            " {\"a\": [1, -2.5e1, true, null, \"\\u00e9\\ud83d\\ude00\\n\"], \"b\": {}} ",
+        )
+        .unwrap();
+        assert_eq!(
+            json,
+            Json::Object(vec![
+                (
+                    "a".to_owned(),
+                    Json::Array(vec![
+                        Json::Integer(1),
+                        Json::Float(-25.0),
+                        Json::Bool(true),
+                        Json::Null,
+                        Json::str("é😀\n"),
+                    ])
+                ),
+                ("b".to_owned(), Json::Object(vec![])),
+            ])
+        );
+        assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+
+        let error = Json::parse("{\n \"a\": [1 2]\n}").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid JSON: expected `,` or `]`, found `2`"
+        );
+        assert_eq!(
+            error.span().unwrap().lo,
+            SourceLocation { row: 1, column: 9 }
+        );
+        assert!(Json::parse("[1] 2").is_err());
+        assert!(Json::parse("\"open").is_err());
+        assert!(Json::parse("tru").is_err());
+    }
+
+    #[test]
+    fn documents_round_trip() {
+        let document = Parser::parse(CODEX).unwrap();
+        let text = document.to_json().to_string();
+        let decoded = document_from_json(&text).unwrap();
+        assert_eq!(decoded.entries, document.entries);
+        assert_round_trips(&decoded);
+    }
+
+    #[test]
+    fn spans_are_optional() {
+        let text = "{\"declarations\": [
+            {\"kind\": \"const\", \"name\": \"a\", \"body\": {\"kind\": \"direct\", \"value\":
+                {\"kind\": \"binary\", \"operator\": \"*\",
+                 \"lhs\": {\"kind\": \"binary\", \"operator\": \"-\",
+                          \"lhs\": {\"kind\": \"integer\", \"value\": 1},
+                          \"rhs\": {\"kind\": \"unary\", \"operator\": \"-\",
+                                   \"operand\": {\"kind\": \"float\", \"value\": 2}}},
+                 \"rhs\": {\"kind\": \"member\", \"member\": \"c\",
+                          \"base\": {\"kind\": \"call\", \"name\": \"min\",
+                                    \"arguments\": [{\"kind\": \"identifier\", \"name\": \"b\"}]}}}}},
+            {\"kind\": \"entry\", \"name\": \"squad\",
+             \"fields\": [{\"name\": \"marines\", \"min\": 5, \"max\": 10,
+                          \"constants\": [{\"name\": \"stats\", \"body\": {\"kind\": \"content\",
+                              \"constants\": [{\"name\": \"BS\", \"body\": {\"kind\": \"direct\",
+                                  \"value\": {\"kind\": \"integer\", \"value\": 3}}}]}}]}]},
+            {\"kind\": \"opt\", \"name\": \"captain\", \"fields\": [
+                {\"kind\": \"alt\", \"name\": \"weapon\", \"options\": [{\"name\": \"sword\"}]},
+                {\"kind\": \"opt\", \"name\": \"jump pack\"}]},
+            {\"kind\": \"choice\", \"name\": \"squad\", \"quantity\": 2,
+             \"choices\": [{\"name\": \"marines\", \"quantity\": 5}]}
+        ]}";
+        let document = document_from_json(text).unwrap();
+        assert_eq!(document.entries.len(), 4);
+        assert_eq!(document.entries[0].span(), Span::new());
+        assert_eq!(
+            document.entries[0].to_string(),
+            "const a = (1 - -2.0) * min(b).c\n"
+        );
+        assert_round_trips(&document);
+    }
+
+    #[test]
+    fn invalid_documents() {
+        let error = |text: &str| document_from_json(text).unwrap_err().to_string();
+        assert_eq!(
+            error("{\"declarations\": [{\"kind\": \"alt\", \"name\": \"a\", \"options\": [{\"name\": \"opt\"}]}]}"),
+            "invalid document at `declarations[0].options[0].name`: `opt` is not a valid name"
+        );
+        assert_eq!(
+            error("{\"declarations\": [{\"kind\": \"const\", \"name\": \"a\", \"body\": {\"kind\": \"direct\", \"value\": {\"kind\": \"integer\", \"value\": -1}}}]}"),
+            "invalid document at `declarations[0].body.value.value`: expected a non negative number, use a `-` unary expression"
+        );
+        assert_eq!(
+            error("{\"declarations\": [{\"kind\": \"entry\", \"name\": \"a\", \"fields\": [{\"name\": \"b\", \"min\": 2, \"max\": 1}]}]}"),
+            "invalid document at `declarations[0].fields[0].max`: expected at least `min`"
+        );
+        assert_eq!(
+            error("{\"declarations\": [{\"kind\": \"table\"}]}"),
+            "invalid document at `declarations[0].kind`: unknown kind `table`"
+        );
+        assert_eq!(
+            error("{\"declarations\": [{\"kind\": \"choice\", \"name\": \"a  b\"}]}"),
+            "invalid document at `declarations[0].name`: `a  b` is not a valid name"
+        );
+        assert_eq!(
+            error("{\"declarations\": [{\"kind\": \"const\", \"name\": \"a\", \"body\": {\"kind\": \"content\"}}]}"),
+            "invalid document at `declarations[0].body.constants`: expected at least one constant"
+        );
+        assert_eq!(error("[]"), "invalid document at ``: expected an object");
+    }
+}
@@ -2,6 +2,28 @@ use crate::error::ParserError;
 use crate::token::*;
 use plex::lexer;
 
+// The content of a string literal, or the first escape sequence that is
+// not one of `\"`, `\\` and `\n`. Unknown sequences are rejected rather
+// than kept, since they could not be written back unchanged.
+fn unescape(text: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('"') => unescaped.push('"'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => return Err(format!("\\{}", other)),
+            None => return Err("\\".to_owned()),
+        }
+    }
+    return Ok(unescaped);
+}
+
 lexer! {
     fn next_token(text: 'a) -> TokenKind;
 
@@ -13,9 +35,14 @@ lexer! {
     r#"const"# => TokenKind::Const,
     r#"opt"# => TokenKind::Opt,
     r#"alt"# => TokenKind::Alt,
+    r#"entry"# => TokenKind::Entry,
+    r#"require"# => TokenKind::Require,
 
     r#"or"# => TokenKind::Or,
     r#"and"# => TokenKind::And,
+    r#"not"# => TokenKind::Not,
+    r#"!"# => TokenKind::Not,
+    r#"in"# => TokenKind::In,
     r#"=="# => TokenKind::Equals,
     r#"!="# => TokenKind::Different,
 
@@ -25,11 +52,25 @@ lexer! {
     r#">"# => TokenKind::Greater,
 
 
+    r#"[0-9]*\.[0-9]+([eE][\+\-]?[0-9]+)?|[0-9]+[eE][\+\-]?[0-9]+"# => {
+        match text.parse::<f64>() {
+            Ok(f) if f.is_finite() => TokenKind::Float(f),
+            _ => TokenKind::FloatOverflow(text.to_owned()),
+        }
+    }
+
     r#"[0-9]+"# => {
         if let Ok(i) = text.parse() {
             TokenKind::Integer(i)
         } else {
-            panic!("integer {} is out of range", text)
+            TokenKind::IntegerOverflow(text.to_owned())
+        }
+    }
+
+    r#""([^"\\\n]|\\[^\n])*""# => {
+        match unescape(&text[1..text.len() - 1]) {
+            Ok(s) => TokenKind::Str(s),
+            Err(sequence) => TokenKind::InvalidEscape(sequence),
         }
     }
 
@@ -41,16 +82,21 @@ lexer! {
     r#"/"# => TokenKind::Slash,
     r#"\("# => TokenKind::LParen,
     r#"\)"# => TokenKind::RParen,
+    r#"\["# => TokenKind::LBracket,
+    r#"\]"# => TokenKind::RBracket,
     r#";"# => TokenKind::Semi,
+    r#"\."# => TokenKind::Dot,
+    r#","# => TokenKind::Comma,
     r#"="# => TokenKind::Assign,
 
-    r#"."# => panic!("unexpected character: {}", text),
+    r#"."# => TokenKind::InvalidCharacter(text.to_owned()),
 }
 
 pub struct Lexer<'a> {
     original: &'a str,
     remaining: &'a str,
     location: SourceLocation,
+    keep_comments: bool,
 }
 
 impl<'a> Lexer<'a> {
@@ -59,7 +105,45 @@ impl<'a> Lexer<'a> {
             original: s,
             remaining: s,
             location: SourceLocation { row: 0, column: 0 },
+            keep_comments: false,
+        }
+    }
+
+    // A lexer that also yields the comment tokens, which are otherwise
+    // dropped. Used by tools that must not lose them, such as the formatter.
+    pub fn with_comments(s: &'a str) -> Lexer<'a> {
+        let mut lexer = Lexer::new(s);
+        lexer.keep_comments = true;
+        return lexer;
+    }
+}
+
+impl<'a> Lexer<'a> {
+    // The next token, comments and whitespace included, together with the
+    // exact text it was lexed from.
+    pub fn next_with_text(&mut self) -> Option<(Token, &'a str)> {
+        let (tok, new_remaining) = next_token(self.remaining)?;
+        let begin = self.original.len() - self.remaining.len();
+        let end = self.original.len() - new_remaining.len();
+        self.remaining = new_remaining;
+
+        let lo = self.location;
+        let text = &self.original[begin..end];
+
+        for c in text.chars() {
+            match c {
+                '\n' => {
+                    self.location.row += 1;
+                    self.location.column = 0;
+                }
+                _ => {
+                    self.location.column += 1;
+                }
+            }
         }
+
+        let hi = self.location;
+        return Some((Token::new(tok, lo, hi), text));
     }
 }
 
@@ -67,29 +151,9 @@ impl<'a> Iterator for Lexer<'a> {
     type Item = Token;
     fn next(&mut self) -> Option<Token> {
         loop {
-            let token = if let Some((tok, new_remaining)) = next_token(self.remaining) {
-                let begin = self.original.len() - self.remaining.len();
-                let end = self.original.len() - new_remaining.len();
-                self.remaining = new_remaining;
-
-                let lo = self.location.clone();
-
-                for i in begin..end {
-                    match self.original.chars().nth(i).unwrap() {
-                        '\n' => {
-                            self.location.row = self.location.row + 1;
-                            self.location.column = 0;
-                        }
-                        _ => {
-                            self.location.column = self.location.column + 1;
-                        }
-                    }
-                }
-
-                let hi = self.location.clone();
-                Some(Token::new(tok, lo, hi))
-            } else {
-                return None;
+            let token = match self.next_with_text() {
+                Some((token, _)) => Some(token),
+                None => return None,
             };
 
             if let Some(Token {
@@ -97,7 +161,9 @@ impl<'a> Iterator for Lexer<'a> {
                 span: _span,
             }) = token
             {
-                continue;
+                if !self.keep_comments {
+                    continue;
+                }
             }
 
             return token;
@@ -105,8 +171,15 @@ impl<'a> Iterator for Lexer<'a> {
     }
 }
 
+// Turns the leading whitespace of lines into Indent and Deindent tokens.
+// It reads the tokens of a Lexer, or any other stream of tokens with the
+// same whitespace, line ends and comments, such as the tokens of a syntax
+// tree. Comments are skipped here so the last location read is the end of
+// the source.
 pub struct IndentLexer<'a> {
-    lexer: Lexer<'a>,
+    tokens: Box<dyn Iterator<Item = Token> + 'a>,
+    // Where the last token read ended.
+    end: SourceLocation,
     next_token: Option<Token>,
     current_token: Option<Token>,
     indentation_stack: Vec<i64>,
@@ -115,35 +188,49 @@ pub struct IndentLexer<'a> {
 }
 
 impl<'a> IndentLexer<'a> {
+    fn pull(&mut self) -> Option<Token> {
+        loop {
+            let token = self.tokens.next()?;
+            self.end = token.span.hi;
+            if token.kind != TokenKind::Comment {
+                return Some(token);
+            }
+        }
+    }
+
     fn advance_impl(&mut self) {
         let expelled_token = self.current_token.clone();
         self.current_token = self.next_token.clone();
-        self.next_token = self.lexer.next();
+        self.next_token = self.pull();
 
         while let Some(value) = self
             .current_token
             .as_ref()
             .zip(self.next_token.as_ref())
-            .and_then(|(a, b)| a.try_merge_whitespace(&b))
+            .and_then(|(a, b)| a.try_merge_whitespace(b))
         {
             self.next_token = Some(value);
             self.current_token = self.next_token.clone();
-            self.next_token = self.lexer.next();
+            self.next_token = self.pull();
         }
 
-        self.start_of_line = match expelled_token {
+        self.start_of_line = matches!(
+            expelled_token,
             Some(Token {
                 kind: TokenKind::EndLine,
-                span: _span,
-            }) => true,
-            None => true,
-            _ => false,
-        }
+                span: _,
+            }) | None
+        );
     }
 
     pub fn new(s: &'a str) -> IndentLexer<'a> {
+        return IndentLexer::from_tokens(Lexer::with_comments(s));
+    }
+
+    pub fn from_tokens(tokens: impl Iterator<Item = Token> + 'a) -> IndentLexer<'a> {
         let mut to_return = IndentLexer {
-            lexer: Lexer::new(s),
+            tokens: Box::new(tokens),
+            end: SourceLocation { row: 0, column: 0 },
             next_token: None,
             current_token: None,
             indentation_stack: Vec::new(),
@@ -151,7 +238,7 @@ impl<'a> IndentLexer<'a> {
             deindent_to_emit: Vec::new(),
         };
 
-        to_return.next_token = to_return.lexer.next();
+        to_return.next_token = to_return.pull();
         to_return.advance_impl();
         return to_return;
     }
@@ -172,8 +259,8 @@ impl<'a> IndentLexer<'a> {
             let to_emit = Token {
                 kind: TokenKind::Deindent,
                 span: Span {
-                    lo: self.lexer.location,
-                    hi: self.lexer.location,
+                    lo: self.end,
+                    hi: self.end,
                 },
             };
             self.indentation_stack.pop();
@@ -181,6 +268,22 @@ impl<'a> IndentLexer<'a> {
         }
 
         return match to_return {
+            Some(Token {
+                kind: TokenKind::InvalidCharacter(text),
+                span,
+            }) => Err(ParserError::new_invalid_character(span, text)),
+            Some(Token {
+                kind: TokenKind::IntegerOverflow(text),
+                span,
+            }) => Err(ParserError::new_integer_overflow(span, text)),
+            Some(Token {
+                kind: TokenKind::FloatOverflow(text),
+                span,
+            }) => Err(ParserError::new_float_overflow(span, text)),
+            Some(Token {
+                kind: TokenKind::InvalidEscape(sequence),
+                span,
+            }) => Err(ParserError::new_invalid_escape(span, sequence)),
             Some(value) => Ok(value),
             None => Err(ParserError::new_end_of_token_stream()),
         };
@@ -194,7 +297,7 @@ impl<'a> IndentLexer<'a> {
         let Token { kind, span } = self.current_token.as_ref().unwrap();
 
         let current_white_space = match kind {
-            TokenKind::Whitespace(a) => a.clone(),
+            TokenKind::Whitespace(a) => *a,
             _ => 0,
         };
 
@@ -206,7 +309,7 @@ impl<'a> IndentLexer<'a> {
         if self.indentation_stack.last().unwrap_or(&zero) < &current_white_space {
             let to_emit = Token {
                 kind: TokenKind::Indent,
-                span: span.clone(),
+                span: *span,
             };
 
             self.deindent_to_emit.push(to_emit);
@@ -219,7 +322,7 @@ impl<'a> IndentLexer<'a> {
             let indent = self.indentation_stack.pop().unwrap_or(zero);
             if indent < current_white_space {
                 return Err(ParserError::new_indentation_miss_match(
-                    span.clone(),
+                    *span,
                     indent,
                     current_white_space,
                 ));
@@ -232,7 +335,7 @@ impl<'a> IndentLexer<'a> {
 
             let to_emit = Token {
                 kind: TokenKind::Deindent,
-                span: span.clone(),
+                span: *span,
             };
 
             self.deindent_to_emit.push(to_emit);
@@ -289,7 +392,7 @@ mod tests {
         let maybe_error = option.as_ref().unwrap();
         assert!(maybe_error.is_ok());
         let token = maybe_error.as_ref().unwrap();
-        return token.span.clone();
+        return token.span;
     }
 
     #[test]
@@ -378,4 +481,68 @@ mod tests {
         assert_eq!(token_kind(&lexer.next()), TokenKind::Deindent);
         assert!(lexer.next().is_none());
     }
+
+    #[test]
+    fn string_test() {
+        let mut lexer = IndentLexer::new("\"squad \\\"alpha\\\"\\\\\\n\"");
+        assert_eq!(
+            token_kind(&lexer.next()),
+            TokenKind::Str("squad \"alpha\"\\\n".to_owned())
+        );
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn float_test() {
+        let mut lexer = IndentLexer::new("1.5 .5 1e3 2.5E-1 7");
+        assert_eq!(token_kind(&lexer.next()), TokenKind::Float(1.5));
+        assert_eq!(token_kind(&lexer.next()), TokenKind::Float(0.5));
+        assert_eq!(token_kind(&lexer.next()), TokenKind::Float(1000.0));
+        assert_eq!(token_kind(&lexer.next()), TokenKind::Float(0.25));
+        assert_eq!(token_kind(&lexer.next()), TokenKind::Integer(7));
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn invalid_character_test() {
+        let mut lexer = IndentLexer::new("a $ b");
+        assert_eq!(token_kind(&lexer.next()), TokenKind::Ident("a".to_owned()));
+        let error = lexer.next().unwrap().unwrap_err();
+        let invalid = error.get_invalid_character().unwrap();
+        assert_eq!(invalid.character, "$");
+        assert_eq!(invalid.span.lo.column, 2);
+        assert_eq!(token_kind(&lexer.next()), TokenKind::Ident("b".to_owned()));
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn integer_overflow_test() {
+        let mut lexer = IndentLexer::new("99999999999999999999");
+        let error = lexer.next().unwrap().unwrap_err();
+        assert!(error.is_integer_overflow());
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn float_overflow_test() {
+        let mut lexer = IndentLexer::new("1.5e400");
+        let error = lexer.next().unwrap().unwrap_err();
+        assert!(error.is_float_overflow());
+        assert!(lexer.next().is_none());
+
+        let mut lexer = IndentLexer::new("1.5e-400");
+        assert!(lexer.next().unwrap().is_ok());
+    }
+
+    #[test]
+    fn unknown_escape_test() {
+        let mut lexer = IndentLexer::new(r#""a\tb""#);
+        let error = lexer.next().unwrap().unwrap_err();
+        assert_eq!(error.get_invalid_escape().unwrap().sequence, "\\t");
+        assert_eq!(error.to_string(), "unknown escape sequence `\\t` in string");
+
+        let mut lexer = IndentLexer::new(r#""\"\\\n""#);
+        let token = lexer.next().unwrap().unwrap();
+        assert_eq!(token.kind, TokenKind::Str("\"\\\n".to_owned()));
+    }
 }
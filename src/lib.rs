@@ -1,16 +1,30 @@
 #![feature(proc_macro_hygiene)]
+#![allow(clippy::needless_return)]
 extern crate plex;
 
 pub mod alternative;
+pub mod builtin;
 pub mod choice;
+pub mod cli;
 pub mod constant;
+pub mod cost;
+pub mod cst;
 pub mod declaration;
+pub mod diagnostic;
 pub mod document;
+pub mod entry;
 pub mod error;
 pub mod expression;
+pub mod format;
+pub mod json;
 pub mod lexer;
+pub mod lsp;
 pub mod option;
 pub mod parser;
+pub mod requirement;
 pub mod serialization;
 pub mod symbol_table;
 pub mod token;
+pub mod types;
+pub mod validation;
+pub mod value;
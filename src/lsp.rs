@@ -0,0 +1,1076 @@
+use crate::alternative::*;
+use crate::builtin::BUILTINS;
+use crate::choice::*;
+use crate::constant::*;
+use crate::declaration::*;
+use crate::diagnostic::end_of_source;
+use crate::document::Document;
+use crate::entry::*;
+use crate::error::*;
+use crate::json::Json;
+use crate::lexer::Lexer;
+use crate::option::*;
+use crate::parser::Parser;
+use crate::symbol_table::*;
+use crate::token::*;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// Clients send the whole text of a document on every change.
+const FULL_SYNC: i64 = 1;
+
+const ERROR_SEVERITY: i64 = 1;
+const WARNING_SEVERITY: i64 = 2;
+
+const KEYWORD_COMPLETION: i64 = 14;
+const FUNCTION_COMPLETION: i64 = 3;
+
+const KEYWORDS: [&str; 9] = [
+    "const", "opt", "alt", "entry", "require", "and", "or", "not", "in",
+];
+
+// Reads the body of the next message, framed by a `Content-Length`
+// header. Returns None once the client has closed the stream.
+pub fn read_message(input: &mut dyn BufRead) -> std::io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "missing Content-Length header",
+        )
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    return String::from_utf8(body)
+        .map(Some)
+        .map_err(|x| std::io::Error::new(std::io::ErrorKind::InvalidData, x));
+}
+
+pub fn write_message(output: &mut dyn Write, message: &Json) -> std::io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    return output.flush();
+}
+
+// Rows and columns map directly to LSP lines and characters. Columns count
+// characters, which are the UTF-16 offsets clients send for every
+// character of the basic multilingual plane.
+fn position(location: SourceLocation) -> Json {
+    return Json::object(vec![
+        ("line", Json::Integer(location.row as i64)),
+        ("character", Json::Integer(location.column as i64)),
+    ]);
+}
+
+fn range(span: Span) -> Json {
+    return Json::object(vec![
+        ("start", position(span.lo)),
+        ("end", position(span.hi)),
+    ]);
+}
+
+fn before(a: SourceLocation, b: SourceLocation) -> bool {
+    return (a.row, a.column) <= (b.row, b.column);
+}
+
+fn contains(span: Span, location: SourceLocation) -> bool {
+    return before(span.lo, location) && before(location, span.hi);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutlineKind {
+    Const,
+    Opt,
+    Alt,
+    Entry,
+    Field,
+    Choice,
+}
+
+impl OutlineKind {
+    fn symbol_kind(&self) -> i64 {
+        return match self {
+            OutlineKind::Entry => 5,
+            OutlineKind::Field => 8,
+            OutlineKind::Alt => 10,
+            OutlineKind::Const => 14,
+            OutlineKind::Choice => 19,
+            OutlineKind::Opt => 23,
+        };
+    }
+
+    fn completion_kind(&self) -> i64 {
+        return match self {
+            OutlineKind::Field => 5,
+            OutlineKind::Entry => 7,
+            OutlineKind::Choice => 12,
+            OutlineKind::Alt => 13,
+            OutlineKind::Const => 21,
+            OutlineKind::Opt => 22,
+        };
+    }
+}
+
+// A named declaration and the named declarations nested in it. The span
+// covers the whole declaration, the selection only its name.
+#[derive(Debug, Clone)]
+struct Outline {
+    kind: OutlineKind,
+    name: String,
+    signature: String,
+    span: Span,
+    selection: Span,
+    children: Vec<Outline>,
+}
+
+impl Outline {
+    // Choices select declarations rather than declare them, so they are
+    // never the target of a name.
+    fn child(&self, name: &str) -> Option<&Outline> {
+        let name = normalize_name(name);
+        return self
+            .children
+            .iter()
+            .find(|x| x.kind != OutlineKind::Choice && x.name == name);
+    }
+
+    fn to_json(&self) -> Json {
+        return Json::object(vec![
+            ("name", Json::str(&self.name)),
+            ("detail", Json::str(&self.signature)),
+            ("kind", Json::Integer(self.kind.symbol_kind())),
+            ("range", range(self.span)),
+            ("selectionRange", range(self.selection)),
+            (
+                "children",
+                Json::Array(self.children.iter().map(|x| x.to_json()).collect()),
+            ),
+        ]);
+    }
+}
+
+// The tokens of a source, without whitespace and line breaks.
+fn tokens(source: &str) -> Vec<Token> {
+    return Lexer::new(source)
+        .filter(|x| !x.is_whitespace() && x.kind != TokenKind::EndLine)
+        .collect();
+}
+
+fn is_identifier(token: &Token) -> bool {
+    return matches!(token.kind, TokenKind::Ident(_));
+}
+
+// The identifiers around `index` that are on the same row, a name such as
+// `marine profile` is made of several of them.
+fn name_around(tokens: &[Token], index: usize) -> (usize, usize) {
+    let row = tokens[index].span.lo.row;
+    let same_name = |x: &Token| is_identifier(x) && x.span.lo.row == row;
+    let mut first = index;
+    while first > 0 && same_name(&tokens[first - 1]) {
+        first -= 1;
+    }
+    let mut last = index;
+    while last + 1 < tokens.len() && same_name(&tokens[last + 1]) {
+        last += 1;
+    }
+    return (first, last);
+}
+
+fn name_text(tokens: &[Token], first: usize, last: usize) -> String {
+    let words: Vec<String> = tokens[first..=last]
+        .iter()
+        .filter_map(|x| x.get_identifier())
+        .collect();
+    return words.join(" ");
+}
+
+// The names of a member access ending with the name whose last identifier
+// is at `last`, `marine profile.WS` gives `marine profile` and `WS`.
+fn path_ending_at(tokens: &[Token], last: usize) -> Vec<String> {
+    let mut path = Vec::new();
+    let mut last = last;
+    loop {
+        let (first, _) = name_around(tokens, last);
+        path.insert(0, name_text(tokens, first, last));
+        if first < 2
+            || tokens[first - 1].kind != TokenKind::Dot
+            || !is_identifier(&tokens[first - 2])
+        {
+            return path;
+        }
+        last = first - 2;
+    }
+}
+
+// The span of the name of a declaration, the first identifiers after its
+// start.
+fn name_span(tokens: &[Token], span: Span) -> Span {
+    let index = tokens
+        .iter()
+        .position(|x| is_identifier(x) && before(span.lo, x.span.lo));
+    return match index {
+        Some(index) if tokens[index].span.lo.row == span.lo.row => {
+            let (first, last) = name_around(tokens, index);
+            tokens[first].span.merge(&tokens[last].span)
+        }
+        _ => span,
+    };
+}
+
+struct OutlineBuilder<'a> {
+    tokens: &'a [Token],
+}
+
+impl<'a> OutlineBuilder<'a> {
+    fn outline(
+        &self,
+        kind: OutlineKind,
+        name: &str,
+        signature: String,
+        span: Span,
+        children: Vec<Outline>,
+    ) -> Outline {
+        return Outline {
+            kind,
+            name: normalize_name(name),
+            signature,
+            span,
+            selection: name_span(self.tokens, span),
+            children,
+        };
+    }
+
+    fn constant(&self, constant: &ConstantDeclaration) -> Outline {
+        let children = constant
+            .get_fields()
+            .into_iter()
+            .flatten()
+            .map(|x| self.constant(x))
+            .collect();
+        let signature = format!("const {}", constant.name());
+        return self.outline(
+            OutlineKind::Const,
+            constant.name(),
+            signature,
+            constant.span(),
+            children,
+        );
+    }
+
+    fn option(&self, option: &OptionDeclaration) -> Outline {
+        let children = option
+            .get_fields()
+            .iter()
+            .filter_map(|x| match x {
+                OptionField::SubOption(option) => Some(self.option(option)),
+                OptionField::Const(constant) => Some(self.constant(constant)),
+                OptionField::Alt(alternative) => Some(self.alternative(alternative)),
+                OptionField::Require(_) => None,
+            })
+            .collect();
+        let signature = format!("opt {}", option.name());
+        return self.outline(
+            OutlineKind::Opt,
+            option.name(),
+            signature,
+            option.span(),
+            children,
+        );
+    }
+
+    fn alternative(&self, alternative: &AlternativeDeclaration) -> Outline {
+        let children = alternative
+            .get_fields()
+            .iter()
+            .map(|x| self.option(x))
+            .collect();
+        let signature = format!("alt {}", alternative.name());
+        return self.outline(
+            OutlineKind::Alt,
+            alternative.name(),
+            signature,
+            alternative.span(),
+            children,
+        );
+    }
+
+    fn entry_field(&self, field: &EntryField) -> Outline {
+        let children = field
+            .get_fields()
+            .iter()
+            .map(|x| self.constant(x))
+            .collect();
+        let signature = match field.min() == field.max() {
+            true => format!("{} {}", field.min(), field.name()),
+            false => format!("{}-{} {}", field.min(), field.max(), field.name()),
+        };
+        return self.outline(
+            OutlineKind::Field,
+            field.name(),
+            signature,
+            field.span(),
+            children,
+        );
+    }
+
+    fn entry(&self, entry: &EntryDeclaration) -> Outline {
+        let constants = entry.get_constants().iter().map(|x| self.constant(x));
+        let fields = entry.get_fields().iter().map(|x| self.entry_field(x));
+        let signature = format!("entry {}", entry.name());
+        return self.outline(
+            OutlineKind::Entry,
+            entry.name(),
+            signature,
+            entry.span(),
+            constants.chain(fields).collect(),
+        );
+    }
+
+    fn choice(&self, choice: &ChoiseDeclaration) -> Outline {
+        let children = choice.get_fields().iter().map(|x| self.choice(x)).collect();
+        let signature = match choice.quantity() {
+            Some(quantity) => format!("{} {}", quantity, choice.symbol()),
+            None => choice.symbol().clone(),
+        };
+        return self.outline(
+            OutlineKind::Choice,
+            choice.symbol(),
+            signature,
+            choice.span(),
+            children,
+        );
+    }
+
+    fn declaration(&self, declaration: &Declaration) -> Option<Outline> {
+        return match declaration {
+            Declaration::Const(constant) => Some(self.constant(constant)),
+            Declaration::Opt(option) => Some(self.option(option)),
+            Declaration::Alt(alternative) => Some(self.alternative(alternative)),
+            Declaration::Entry(entry) => Some(self.entry(entry)),
+            Declaration::Choice(choice) => Some(self.choice(choice)),
+            Declaration::Require(_) => None,
+        };
+    }
+}
+
+// What the server knows about the current text of a document. Documents
+// that do not parse are recovered, so that the declarations before and
+// after an error can still be navigated.
+struct Analysis {
+    source: String,
+    document: Document,
+    diagnostics: Vec<(ParserError, i64)>,
+    tokens: Vec<Token>,
+    outline: Vec<Outline>,
+}
+
+impl Analysis {
+    fn new(source: &str) -> Analysis {
+        let (document, errors) = Parser::parse_with_recovery(source);
+        let mut diagnostics: Vec<(ParserError, i64)> =
+            errors.into_iter().map(|x| (x, ERROR_SEVERITY)).collect();
+        // A recovered document misses declarations, type checking it would
+        // report names that are declared.
+        if diagnostics.is_empty() {
+            match document.type_check_with_warnings() {
+                Ok(warnings) => {
+                    diagnostics.extend(warnings.into_iter().map(|x| (x, WARNING_SEVERITY)))
+                }
+                Err(error) => diagnostics.push((error, ERROR_SEVERITY)),
+            }
+        }
+        let tokens = tokens(source);
+        let builder = OutlineBuilder { tokens: &tokens };
+        let outline = document
+            .entries
+            .iter()
+            .filter_map(|x| builder.declaration(x))
+            .collect();
+        return Analysis {
+            source: source.to_owned(),
+            document,
+            diagnostics,
+            tokens,
+            outline,
+        };
+    }
+
+    fn diagnostics(&self) -> Json {
+        let diagnostics = self.diagnostics.iter().map(|(error, severity)| {
+            let span = error.span().unwrap_or_else(|| end_of_source(&self.source));
+            return Json::object(vec![
+                ("range", range(span)),
+                ("severity", Json::Integer(*severity)),
+                ("source", Json::str("odl")),
+                ("message", Json::str(&error.to_string())),
+            ]);
+        });
+        return Json::Array(diagnostics.collect());
+    }
+
+    // The declarations containing a location, outermost first.
+    fn scopes(&self, location: SourceLocation) -> Vec<&Outline> {
+        let mut scopes = Vec::new();
+        let mut children = &self.outline;
+        while let Some(scope) = children.iter().find(|x| contains(x.span, location)) {
+            scopes.push(scope);
+            children = &scope.children;
+        }
+        return scopes;
+    }
+
+    // Resolves the first name of a path in the declarations around the
+    // location, then in the top level symbol table, and every other name
+    // as a member of the previous one.
+    fn resolve(&self, path: &[String], location: SourceLocation) -> Option<&Outline> {
+        let (first, members) = path.split_first()?;
+        let scopes = self.scopes(location);
+        let mut found = scopes.iter().rev().find_map(|x| x.child(first));
+        if found.is_none() {
+            let mut table = SymbolTable::new();
+            for declaration in &self.document.entries {
+                // Duplicates are already reported as diagnostics.
+                let _ = table.insert(declaration);
+            }
+            let span = table.get(first)?.span();
+            found = self.outline.iter().find(|x| x.span == span);
+        }
+        for member in members {
+            found = found?.child(member);
+        }
+        return found;
+    }
+
+    // The path under the cursor. On the name of a choice, the path is made
+    // of the choices it is nested in, since a choice selects the entry or
+    // field with its name.
+    fn path_at(&self, location: SourceLocation) -> Option<(Vec<String>, Span)> {
+        let scopes = self.scopes(location);
+        if let Some(choice) = scopes.last() {
+            if choice.kind == OutlineKind::Choice && contains(choice.selection, location) {
+                let path = scopes.iter().map(|x| x.name.clone()).collect();
+                return Some((path, choice.selection));
+            }
+        }
+        let index = self
+            .tokens
+            .iter()
+            .position(|x| is_identifier(x) && contains(x.span, location))?;
+        let (first, last) = name_around(&self.tokens, index);
+        let span = self.tokens[first].span.merge(&self.tokens[last].span);
+        return Some((path_ending_at(&self.tokens, last), span));
+    }
+
+    fn hover(&self, location: SourceLocation) -> Json {
+        let (path, span) = match self.path_at(location) {
+            Some(found) => found,
+            None => return Json::Null,
+        };
+        let outline = match self.resolve(&path, location) {
+            Some(outline) => outline,
+            None => return Json::Null,
+        };
+        let mut signature = outline.signature.clone();
+        if outline.kind == OutlineKind::Const && self.diagnostics.is_empty() {
+            if let Ok(value) = self.document.evaluate(&path.join(".")) {
+                signature = format!("{} = {}", signature, value);
+            }
+        }
+        let contents = Json::object(vec![
+            ("kind", Json::str("markdown")),
+            ("value", Json::Str(format!("```odl\n{}\n```", signature))),
+        ]);
+        return Json::object(vec![("contents", contents), ("range", range(span))]);
+    }
+
+    fn definition(&self, uri: &str, location: SourceLocation) -> Json {
+        let outline = self
+            .path_at(location)
+            .and_then(|(path, _)| self.resolve(&path, location));
+        return match outline {
+            Some(outline) => Json::object(vec![
+                ("uri", Json::str(uri)),
+                ("range", range(outline.selection)),
+            ]),
+            None => Json::Null,
+        };
+    }
+
+    fn symbols(&self) -> Json {
+        return Json::Array(self.outline.iter().map(|x| x.to_json()).collect());
+    }
+
+    // After a dot, the members of the declaration before it. Anywhere else,
+    // the names in scope, the builtins and the keywords.
+    fn completion(&self, location: SourceLocation) -> Json {
+        let mut items: Vec<(String, i64)> = Vec::new();
+        let previous = self
+            .tokens
+            .iter()
+            .rposition(|x| before(x.span.hi, location));
+        let mut previous = previous;
+        if let Some(index) = previous {
+            let token = &self.tokens[index];
+            if is_identifier(token) && token.span.hi == location {
+                let (first, _) = name_around(&self.tokens, index);
+                previous = first.checked_sub(1);
+            }
+        }
+        let member_of = previous.filter(|x| {
+            *x > 0 && self.tokens[*x].kind == TokenKind::Dot && is_identifier(&self.tokens[x - 1])
+        });
+        match member_of {
+            Some(dot) => {
+                let path = path_ending_at(&self.tokens, dot - 1);
+                if let Some(outline) = self.resolve(&path, location) {
+                    for child in &outline.children {
+                        items.push((child.name.clone(), child.kind.completion_kind()));
+                    }
+                }
+            }
+            None => {
+                let scopes = self.scopes(location);
+                let visible = scopes.iter().rev().flat_map(|x| x.children.iter());
+                for outline in visible.chain(self.outline.iter()) {
+                    if outline.kind != OutlineKind::Choice {
+                        items.push((outline.name.clone(), outline.kind.completion_kind()));
+                    }
+                }
+                for builtin in BUILTINS.iter() {
+                    items.push((builtin.name().to_owned(), FUNCTION_COMPLETION));
+                }
+                for keyword in KEYWORDS.iter() {
+                    items.push((keyword.to_string(), KEYWORD_COMPLETION));
+                }
+            }
+        }
+        let mut seen = Vec::new();
+        let mut completions = Vec::new();
+        for (label, kind) in items {
+            if seen.contains(&label) {
+                continue;
+            }
+            completions.push(Json::object(vec![
+                ("label", Json::str(&label)),
+                ("kind", Json::Integer(kind)),
+            ]));
+            seen.push(label);
+        }
+        return Json::Array(completions);
+    }
+}
+
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+impl ResponseError {
+    fn new(code: i64, message: &str) -> ResponseError {
+        return ResponseError {
+            code,
+            message: message.to_owned(),
+        };
+    }
+}
+
+fn response(id: &Json, result: Result<Json, ResponseError>) -> Json {
+    let outcome = match result {
+        Ok(result) => ("result", result),
+        Err(error) => (
+            "error",
+            Json::object(vec![
+                ("code", Json::Integer(error.code)),
+                ("message", Json::Str(error.message)),
+            ]),
+        ),
+    };
+    return Json::object(vec![
+        ("jsonrpc", Json::str("2.0")),
+        ("id", id.clone()),
+        outcome,
+    ]);
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    return Json::object(vec![
+        ("jsonrpc", Json::str("2.0")),
+        ("method", Json::str(method)),
+        ("params", params),
+    ]);
+}
+
+fn uri(params: &Json) -> Option<&str> {
+    return params.get("textDocument")?.get("uri")?.as_str();
+}
+
+fn location(params: &Json) -> Option<SourceLocation> {
+    let position = params.get("position")?;
+    let row = position.get("line")?.as_integer()?;
+    let column = position.get("character")?.as_integer()?;
+    return Some(SourceLocation {
+        row: usize::try_from(row).ok()?,
+        column: usize::try_from(column).ok()?,
+    });
+}
+
+// A language server for the documents opened by a single client. Every
+// message is answered with the messages to send back, diagnostics are
+// published whenever a document is opened or changed.
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    shutdown: bool,
+    exit: bool,
+}
+
+impl Server {
+    pub fn new() -> Server {
+        return Server::default();
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        return self.shutdown;
+    }
+
+    pub fn has_exited(&self) -> bool {
+        return self.exit;
+    }
+
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(|x| x.as_str());
+        let params = message.get("params").unwrap_or(&Json::Null);
+        return match (message.get("id"), method) {
+            (Some(id), Some(method)) => vec![response(id, self.request(method, params))],
+            (None, Some(method)) => self.notification(method, params),
+            // Responses to requests the server never sends.
+            (Some(_), None) if message.get("result").is_some() => Vec::new(),
+            (Some(_), None) if message.get("error").is_some() => Vec::new(),
+            (id, None) => {
+                let error = ResponseError::new(INVALID_REQUEST, "missing method");
+                vec![response(id.unwrap_or(&Json::Null), Err(error))]
+            }
+        };
+    }
+
+    fn analysis(&self, params: &Json) -> Result<Analysis, ResponseError> {
+        let uri = uri(params).ok_or_else(|| ResponseError::new(INVALID_PARAMS, "missing uri"))?;
+        return match self.documents.get(uri) {
+            Some(text) => Ok(Analysis::new(text)),
+            None => Err(ResponseError::new(
+                INVALID_PARAMS,
+                &format!("unknown document `{}`", uri),
+            )),
+        };
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, ResponseError> {
+        if self.shutdown {
+            return Err(ResponseError::new(
+                INVALID_REQUEST,
+                "the server is shut down",
+            ));
+        }
+        let missing_position = || ResponseError::new(INVALID_PARAMS, "missing position");
+        return match method {
+            "initialize" => Ok(Json::object(vec![
+                (
+                    "capabilities",
+                    Json::object(vec![
+                        ("textDocumentSync", Json::Integer(FULL_SYNC)),
+                        ("hoverProvider", Json::Bool(true)),
+                        ("definitionProvider", Json::Bool(true)),
+                        ("documentSymbolProvider", Json::Bool(true)),
+                        (
+                            "completionProvider",
+                            Json::object(vec![(
+                                "triggerCharacters",
+                                Json::Array(vec![Json::str(".")]),
+                            )]),
+                        ),
+                    ]),
+                ),
+                (
+                    "serverInfo",
+                    Json::object(vec![
+                        ("name", Json::str("odl-lsp")),
+                        ("version", Json::str(env!("CARGO_PKG_VERSION"))),
+                    ]),
+                ),
+            ])),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/hover" => {
+                let location = location(params).ok_or_else(missing_position)?;
+                Ok(self.analysis(params)?.hover(location))
+            }
+            "textDocument/definition" => {
+                let location = location(params).ok_or_else(missing_position)?;
+                let uri = uri(params).unwrap_or("");
+                Ok(self.analysis(params)?.definition(uri, location))
+            }
+            "textDocument/documentSymbol" => Ok(self.analysis(params)?.symbols()),
+            "textDocument/completion" => {
+                let location = location(params).ok_or_else(missing_position)?;
+                Ok(self.analysis(params)?.completion(location))
+            }
+            _ => Err(ResponseError::new(
+                METHOD_NOT_FOUND,
+                &format!("unknown method `{}`", method),
+            )),
+        };
+    }
+
+    // Malformed notifications cannot be answered, they are ignored.
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        if method == "exit" {
+            self.exit = true;
+            return Vec::new();
+        }
+        let uri = match uri(params) {
+            Some(uri) => uri.to_owned(),
+            None => return Vec::new(),
+        };
+        let text = match method {
+            "textDocument/didOpen" => params
+                .get("textDocument")
+                .and_then(|x| x.get("text"))
+                .and_then(|x| x.as_str()),
+            // With full sync the last change holds the whole text.
+            "textDocument/didChange" => match params.get("contentChanges") {
+                Some(Json::Array(changes)) => changes
+                    .last()
+                    .and_then(|x| x.get("text"))
+                    .and_then(|x| x.as_str()),
+                _ => None,
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![self.publish(&uri, Json::Array(Vec::new()))];
+            }
+            _ => None,
+        };
+        return match text {
+            Some(text) => {
+                let diagnostics = Analysis::new(text).diagnostics();
+                self.documents.insert(uri.clone(), text.to_owned());
+                vec![self.publish(&uri, diagnostics)]
+            }
+            None => Vec::new(),
+        };
+    }
+
+    fn publish(&self, uri: &str, diagnostics: Json) -> Json {
+        let params = Json::object(vec![("uri", Json::str(uri)), ("diagnostics", diagnostics)]);
+        return notification("textDocument/publishDiagnostics", params);
+    }
+}
+
+// Serves a client until it sends `exit` or closes the stream. Returns the
+// process exit code, which is 0 only if the client asked for a shutdown
+// first.
+pub fn serve(input: &mut dyn BufRead, output: &mut dyn Write) -> std::io::Result<i32> {
+    let mut server = Server::new();
+    while let Some(body) = read_message(input)? {
+        let replies = match Json::parse(&body) {
+            Ok(message) => server.handle(&message),
+            Err(error) => {
+                let error = ResponseError::new(PARSE_ERROR, &error.to_string());
+                vec![response(&Json::Null, Err(error))]
+            }
+        };
+        for reply in &replies {
+            write_message(output, reply)?;
+        }
+        if server.has_exited() {
+            break;
+        }
+    }
+    return Ok(if server.is_shutdown() { 0 } else { 1 });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lsp::*;
+
+    const CODEX: &str = "const marine profile\n WS = 4\n BS = 3\nentry squad\n const cost = marine profile.WS\n 5-10 marines\n  profile = marine profile\n";
+
+    fn frame(message: &str) -> String {
+        return format!("Content-Length: {}\r\n\r\n{}", message.len(), message);
+    }
+
+    fn open(uri: &str, text: &str) -> String {
+        let text = Json::str(text);
+        return format!(
+            "{{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/didOpen\",\"params\":{{\"textDocument\":{{\"uri\":\"{}\",\"languageId\":\"odl\",\"version\":1,\"text\":{}}}}}}}",
+            uri, text
+        );
+    }
+
+    fn at(id: i64, method: &str, row: usize, column: usize) -> String {
+        return format!(
+            "{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"{}\",\"params\":{{\"textDocument\":{{\"uri\":\"file:///codex.odl\"}},\"position\":{{\"line\":{},\"character\":{}}}}}}}",
+            id, method, row, column
+        );
+    }
+
+    // Plays the messages of a client against the server, as it would over
+    // stdio, and returns the exit code with the messages sent back.
+    fn session(messages: &[String]) -> (i32, Vec<Json>) {
+        let input: String = messages.iter().map(|x| frame(x)).collect();
+        let mut output = Vec::new();
+        let code = serve(&mut input.as_bytes(), &mut output).unwrap();
+        let mut output = output.as_slice();
+        let mut replies = Vec::new();
+        while let Some(body) = read_message(&mut output).unwrap() {
+            replies.push(Json::parse(&body).unwrap());
+        }
+        return (code, replies);
+    }
+
+    fn result(reply: &Json) -> &Json {
+        return reply.get("result").unwrap();
+    }
+
+    fn start(row: &Json) -> (i64, i64) {
+        let start = row.get("start").unwrap();
+        return (
+            start.get("line").unwrap().as_integer().unwrap(),
+            start.get("character").unwrap().as_integer().unwrap(),
+        );
+    }
+
+    #[test]
+    fn message_framing() {
+        let message = Json::object(vec![("id", Json::Integer(1))]);
+        let mut output = Vec::new();
+        write_message(&mut output, &message).unwrap();
+        assert_eq!(output, b"Content-Length: 8\r\n\r\n{\"id\":1}");
+
+        let input = "Content-Type: application/vscode-jsonrpc\r\ncontent-length: 2\r\n\r\n{}";
+        let mut input = input.as_bytes();
+        assert_eq!(read_message(&mut input).unwrap().unwrap(), "{}");
+        assert_eq!(read_message(&mut input).unwrap(), None);
+        assert!(read_message(&mut "\r\n{}".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn lifecycle() {
+        let (code, replies) = session(&[
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\",\"params\":{}}".to_owned(),
+            "{\"jsonrpc\":\"2.0\",\"method\":\"initialized\",\"params\":{}}".to_owned(),
+            "{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"workspace/symbol\"}".to_owned(),
+            "{not json".to_owned(),
+            "{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"shutdown\"}".to_owned(),
+            "{\"jsonrpc\":\"2.0\",\"id\":4,\"method\":\"textDocument/hover\"}".to_owned(),
+            "{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}".to_owned(),
+            "{\"jsonrpc\":\"2.0\",\"id\":5,\"method\":\"shutdown\"}".to_owned(),
+        ]);
+        assert_eq!(code, 0);
+        assert_eq!(replies.len(), 5);
+        let capabilities = result(&replies[0]).get("capabilities").unwrap();
+        assert_eq!(
+            capabilities.get("textDocumentSync"),
+            Some(&Json::Integer(1))
+        );
+        assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+        let code = |x: &Json| x.get("error").unwrap().get("code").unwrap().as_integer();
+        assert_eq!(code(&replies[1]), Some(-32601));
+        assert_eq!(code(&replies[2]), Some(-32700));
+        assert_eq!(replies[2].get("id"), Some(&Json::Null));
+        assert_eq!(result(&replies[3]), &Json::Null);
+        assert_eq!(code(&replies[4]), Some(-32600));
+
+        let (code, _) = session(&["{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}".to_owned()]);
+        assert_eq!(code, 1);
+    }
+
+    #[test]
+    fn publishes_diagnostics() {
+        let change = |text: &str| {
+            format!(
+                "{{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/didChange\",\"params\":{{\"textDocument\":{{\"uri\":\"file:///a.odl\",\"version\":2}},\"contentChanges\":[{{\"text\":{}}}]}}}}",
+                Json::str(text)
+            )
+        };
+        let (_, replies) = session(&[
+            open("file:///a.odl", "const = 4\nconst b = 1\n"),
+            change("const a = 4\nconst b = c\n"),
+            change("const a = 4\nentry b\n const a = 1\n"),
+            change("const a = 4\n"),
+            "{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/didClose\",\"params\":{\"textDocument\":{\"uri\":\"file:///a.odl\"}}}".to_owned(),
+        ]);
+        assert_eq!(replies.len(), 5);
+        let diagnostics: Vec<&Vec<Json>> = replies
+            .iter()
+            .map(|x| {
+                assert_eq!(
+                    x.get("method").unwrap().as_str(),
+                    Some("textDocument/publishDiagnostics")
+                );
+                let params = x.get("params").unwrap();
+                assert_eq!(params.get("uri").unwrap().as_str(), Some("file:///a.odl"));
+                match params.get("diagnostics").unwrap() {
+                    Json::Array(diagnostics) => diagnostics,
+                    _ => panic!("expected an array"),
+                }
+            })
+            .collect();
+
+        assert_eq!(diagnostics[0].len(), 1);
+        let error = &diagnostics[0][0];
+        assert_eq!(start(error.get("range").unwrap()), (0, 6));
+        assert_eq!(error.get("severity"), Some(&Json::Integer(1)));
+        assert_eq!(
+            error.get("message").unwrap().as_str(),
+            Some("expected identifier, found `=`")
+        );
+
+        assert_eq!(diagnostics[1].len(), 1);
+        assert_eq!(start(diagnostics[1][0].get("range").unwrap()), (1, 10));
+
+        assert_eq!(diagnostics[2].len(), 1);
+        assert_eq!(diagnostics[2][0].get("severity"), Some(&Json::Integer(2)));
+        assert_eq!(start(diagnostics[2][0].get("range").unwrap()), (2, 1));
+
+        assert!(diagnostics[3].is_empty());
+        assert!(diagnostics[4].is_empty());
+    }
+
+    #[test]
+    fn hover_and_definition() {
+        let (_, replies) = session(&[
+            open("file:///codex.odl", CODEX),
+            at(1, "textDocument/hover", 4, 30),
+            at(2, "textDocument/definition", 4, 30),
+            at(3, "textDocument/definition", 4, 16),
+            at(4, "textDocument/hover", 5, 7),
+            at(5, "textDocument/definition", 4, 12),
+        ]);
+        let hover = result(&replies[1]);
+        let contents = hover.get("contents").unwrap().get("value").unwrap();
+        assert_eq!(contents.as_str(), Some("```odl\nconst WS = 4\n```"));
+        assert_eq!(start(hover.get("range").unwrap()), (4, 29));
+
+        let definition = result(&replies[2]);
+        assert_eq!(
+            definition.get("uri").unwrap().as_str(),
+            Some("file:///codex.odl")
+        );
+        let range = definition.get("range").unwrap();
+        assert_eq!(start(range), (1, 1));
+        assert_eq!(
+            range.get("end"),
+            Some(&Json::object(vec![
+                ("line", Json::Integer(1)),
+                ("character", Json::Integer(3)),
+            ]))
+        );
+        assert_eq!(start(result(&replies[3]).get("range").unwrap()), (0, 6));
+
+        let contents = result(&replies[4]).get("contents").unwrap();
+        assert_eq!(
+            contents.get("value").unwrap().as_str(),
+            Some("```odl\n5-10 marines\n```")
+        );
+        assert_eq!(result(&replies[5]), &Json::Null);
+    }
+
+    #[test]
+    fn choices_resolve_to_what_they_select() {
+        let roster = format!("{}2 squad\n 5 marines\n", CODEX);
+        let (_, replies) = session(&[
+            open("file:///codex.odl", &roster),
+            at(1, "textDocument/definition", 7, 2),
+            at(2, "textDocument/definition", 8, 4),
+        ]);
+        assert_eq!(start(result(&replies[1]).get("range").unwrap()), (3, 6));
+        assert_eq!(start(result(&replies[2]).get("range").unwrap()), (5, 6));
+    }
+
+    #[test]
+    fn document_symbols() {
+        let (_, replies) = session(&[
+            open("file:///codex.odl", CODEX),
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"textDocument/documentSymbol\",\"params\":{\"textDocument\":{\"uri\":\"file:///codex.odl\"}}}".to_owned(),
+        ]);
+        fn tree(symbols: &Json) -> String {
+            let symbols = match symbols {
+                Json::Array(symbols) => symbols,
+                _ => panic!("expected an array"),
+            };
+            let trees: Vec<String> = symbols
+                .iter()
+                .map(|x| {
+                    let name = x.get("name").unwrap().as_str().unwrap();
+                    let kind = x.get("kind").unwrap().as_integer().unwrap();
+                    format!("{}:{}({})", name, kind, tree(x.get("children").unwrap()))
+                })
+                .collect();
+            return trees.join(" ");
+        }
+        let symbols = result(&replies[1]);
+        assert_eq!(
+            tree(symbols),
+            "marine profile:14(WS:14() BS:14()) squad:5(cost:14() marines:8(profile:14()))"
+        );
+        let squad = match symbols {
+            Json::Array(symbols) => &symbols[1],
+            _ => panic!("expected an array"),
+        };
+        assert_eq!(start(squad.get("range").unwrap()), (3, 0));
+        assert_eq!(start(squad.get("selectionRange").unwrap()), (3, 6));
+        assert_eq!(squad.get("detail").unwrap().as_str(), Some("entry squad"));
+    }
+
+    #[test]
+    fn completion() {
+        let (_, replies) = session(&[
+            open("file:///codex.odl", CODEX),
+            at(1, "textDocument/completion", 4, 29),
+            at(2, "textDocument/completion", 4, 30),
+            at(3, "textDocument/completion", 6, 12),
+        ]);
+        let labels = |reply: &Json| -> Vec<String> {
+            match result(reply) {
+                Json::Array(items) => items
+                    .iter()
+                    .map(|x| x.get("label").unwrap().as_str().unwrap().to_owned())
+                    .collect(),
+                _ => panic!("expected an array"),
+            }
+        };
+        assert_eq!(labels(&replies[1]), vec!["WS", "BS"]);
+        assert_eq!(labels(&replies[2]), vec!["WS", "BS"]);
+        let labels = labels(&replies[3]);
+        assert_eq!(
+            labels[..5],
+            ["profile", "cost", "marines", "marine profile", "squad"]
+        );
+        assert!(labels.contains(&"count".to_owned()));
+        assert!(labels.contains(&"entry".to_owned()));
+    }
+}
@@ -1,22 +1,12 @@
-use odl::error::ParserError;
-use odl::expression::*;
-
-fn exit_on_error<T>(arg: Result<T, ParserError>) -> T {
-    return match arg {
-        Ok(parser) => parser,
-        Err(error) => {
-            eprintln!("Error: {:#?}", error);
-            std::process::exit(-1);
-        }
-    };
-}
+#![allow(clippy::needless_return)]
+use odl::cli::*;
 
 fn main() {
-    use odl::parser::Parser;
-    use std::io::Read;
-
-    let mut s = String::new();
-    std::io::stdin().read_to_string(&mut s).unwrap();
-    let result = exit_on_error(Parser::parse(&s));
-    println!("{}", result);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut io = Io {
+        stdin: &mut std::io::stdin(),
+        stdout: &mut std::io::stdout(),
+        stderr: &mut std::io::stderr(),
+    };
+    std::process::exit(run(&args, &mut io));
 }
@@ -1,6 +1,8 @@
 use crate::alternative::*;
 use crate::constant::ConstantDeclaration;
+use crate::cost::check_cost;
 use crate::error::*;
+use crate::requirement::*;
 use crate::serialization::*;
 use crate::symbol_table::*;
 use crate::token::Span;
@@ -12,6 +14,7 @@ pub enum OptionField {
     SubOption(OptionDeclaration),
     Const(ConstantDeclaration),
     Alt(AlternativeDeclaration),
+    Require(RequirementDeclaration),
 }
 
 impl Serializable for OptionField {
@@ -24,6 +27,7 @@ impl Serializable for OptionField {
             OptionField::SubOption(c) => c.serialize(f, ctx),
             OptionField::Const(c) => c.serialize(f, ctx),
             OptionField::Alt(c) => c.serialize(f, ctx),
+            OptionField::Require(c) => c.serialize(f, ctx),
         };
     }
 }
@@ -49,16 +53,16 @@ impl Serializable for OptionDeclaration {
     ) -> Result<(), ::std::fmt::Error> {
         indent(f, ctx.indent)?;
         if ctx.emission_kind != EmissionKind::Opt {
-            write!(f, "opt {}\n", self.name)?;
+            writeln!(f, "opt {}", self.name)?;
         } else {
-            write!(f, "{}\n", self.name)?;
+            writeln!(f, "{}", self.name)?;
         }
 
         for decl in &self.fields {
             decl.serialize(f, &ctx.indented().emitting_option())?;
         }
 
-        write!(f, "\n")?;
+        writeln!(f)?;
 
         return Ok(());
     }
@@ -80,6 +84,10 @@ impl OptionDeclaration {
         self.span = span;
     }
 
+    pub fn span(&self) -> Span {
+        return self.span;
+    }
+
     pub fn name(&self) -> &String {
         return &self.name;
     }
@@ -101,6 +109,31 @@ impl OptionDeclaration {
     }
 
     pub fn type_check(&self, table: &SymbolTable) -> Result<(), ParserError> {
+        let mut scope = table.make_child();
+        for field in &self.fields {
+            match field {
+                OptionField::SubOption(decl) => {
+                    scope.insert_symbol(decl.name(), Symbol::Opt(decl))?
+                }
+                OptionField::Const(decl) => {
+                    scope.insert_symbol(decl.name(), Symbol::Const(decl))?
+                }
+                OptionField::Alt(decl) => scope.insert_symbol(decl.name(), Symbol::Alt(decl))?,
+                OptionField::Require(_) => {}
+            };
+        }
+
+        for field in &self.fields {
+            match field {
+                OptionField::SubOption(decl) => decl.type_check(&scope)?,
+                OptionField::Const(decl) => {
+                    decl.type_check(&scope)?;
+                    check_cost(decl, &scope)?
+                }
+                OptionField::Alt(decl) => decl.type_check(&scope)?,
+                OptionField::Require(decl) => decl.type_check(&scope)?,
+            };
+        }
         Ok(())
     }
 }
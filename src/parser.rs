@@ -3,16 +3,25 @@ use crate::choice::*;
 use crate::constant::*;
 use crate::declaration::*;
 use crate::document::*;
+use crate::entry::*;
 use crate::error::ParserError;
 use crate::expression::*;
-use crate::lexer::IndentLexer;
+use crate::lexer::{IndentLexer, Lexer};
 use crate::option::*;
+use crate::requirement::*;
 use crate::token::*;
 
 pub struct Parser<'a> {
     lexer: IndentLexer<'a>,
     current_token: Option<Result<Token, ParserError>>,
     next_token: Result<Token, ParserError>,
+    // The token after next_token, once something had to look that far.
+    second_token: Option<Result<Token, ParserError>>,
+    depth: usize,
+    consumed: usize,
+    // Set while parsing the elements of an indented list, where an element
+    // ends with its line.
+    line_elements: bool,
 }
 
 #[macro_export]
@@ -37,11 +46,9 @@ macro_rules! peek {
 
 #[macro_export]
 macro_rules! expect {
-    ($parser: expr, $pattern:pat) => {{
-        if !matches!($parser.peek().unwrap().kind, $pattern) {
-            return Err(ParserError::new_unexpected_token(
-                $parser.peek().unwrap().clone(),
-            ));
+    ($parser: expr, $pattern:pat, $expected: expr) => {{
+        if !peek!($parser, $pattern) {
+            return Err($parser.unexpected(&[$expected]));
         }
         let token = $parser.next()?;
         token
@@ -50,14 +57,23 @@ macro_rules! expect {
 
 impl<'a> Parser<'a> {
     pub fn new(s: &'a str) -> Result<Parser<'a>, ParserError> {
-        let mut lexer = IndentLexer::new(s);
+        return Ok(Parser::from_tokens(Lexer::with_comments(s)));
+    }
+
+    // A parser reading an already lexed stream of tokens.
+    pub fn from_tokens(tokens: impl Iterator<Item = Token> + 'a) -> Parser<'a> {
+        let mut lexer = IndentLexer::from_tokens(tokens);
         let next_token = lexer.next_token();
         let current_token = None;
-        return Ok(Parser {
+        return Parser {
             lexer,
             current_token,
             next_token,
-        });
+            second_token: None,
+            depth: 0,
+            consumed: 0,
+            line_elements: false,
+        };
     }
 
     fn peek(&self) -> Option<Token> {
@@ -67,11 +83,41 @@ impl<'a> Parser<'a> {
         };
     }
 
+    fn unexpected(&self, expected: &[&str]) -> ParserError {
+        let expected = expected.iter().map(|x| x.to_string()).collect();
+        return match self.next_token.as_ref() {
+            Err(err) if err.is_end_of_token_stream() => {
+                ParserError::new_unexpected_end_of_token_stream(expected)
+            }
+            Err(err) => err.clone(),
+            Ok(token) => ParserError::new_expected_token(token.clone(), expected),
+        };
+    }
+
+    fn at_end(&self) -> Result<bool, ParserError> {
+        return match self.next_token.as_ref() {
+            Ok(_) => Ok(false),
+            Err(err) if err.is_end_of_token_stream() => Ok(true),
+            Err(err) => Err(err.clone()),
+        };
+    }
+
     fn current_span(&self) -> Result<Span, ParserError> {
         return match self.current_token.as_ref() {
             None => Ok(Span::new()),
             Some(Err(err)) => Err(err.clone()),
-            Some(Ok(token)) => Ok(token.span.clone()),
+            Some(Ok(token)) => Ok(token.span),
+        };
+    }
+
+    // Span of the lookahead token, used as the start of the construct that
+    // is about to be parsed.
+    fn next_span(&self) -> Span {
+        return match self.next_token.as_ref() {
+            Ok(token) => token.span,
+            Err(err) => err
+                .span()
+                .unwrap_or_else(|| self.current_span().unwrap_or_default()),
         };
     }
 
@@ -80,182 +126,364 @@ impl<'a> Parser<'a> {
     }
 
     fn next(&mut self) -> Result<Token, ParserError> {
+        match self.next_token.as_ref().map(|x| &x.kind) {
+            Ok(TokenKind::Indent) => self.depth += 1,
+            Ok(TokenKind::Deindent) => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        };
+        self.consumed += 1;
         self.current_token = Some(self.next_token.clone());
-        self.next_token = self.lexer.next_token();
+        self.next_token = match self.second_token.take() {
+            Some(token) => token,
+            None => self.lexer.next_token(),
+        };
         return self.current_token.clone().unwrap();
     }
 
+    // The token following the lookahead.
+    fn peek_second(&mut self) -> Option<Token> {
+        if self.second_token.is_none() {
+            self.second_token = Some(self.lexer.next_token());
+        }
+        return self.second_token.as_ref().unwrap().as_ref().ok().cloned();
+    }
+
+    // Skips tokens until the first token of a line at indentation zero, so
+    // that parsing can resume with the next top level declaration. Lexical
+    // errors met while skipping are appended to errors.
+    fn synchronize(&mut self, consumed_before: usize, errors: &mut Vec<ParserError>) {
+        let mut last_row = match self.current_token.as_ref() {
+            Some(Ok(token)) => Some(token.span.hi.row),
+            _ => None,
+        };
+
+        // Either the lookahead holds the error that was just reported, or
+        // nothing was consumed; in both cases step over one token so that
+        // the parser always makes progress.
+        if self.next_token.is_err() || self.consumed == consumed_before {
+            if let Ok(token) = self.next() {
+                last_row = Some(token.span.hi.row);
+            }
+        }
+
+        loop {
+            match self.next_token.as_ref() {
+                Err(err) if err.is_end_of_token_stream() => break,
+                Err(err) => errors.push(err.clone()),
+                Ok(token) => {
+                    let new_line = last_row.is_none_or(|row| token.span.lo.row > row);
+                    let block_token = matches!(token.kind, TokenKind::Indent | TokenKind::Deindent);
+                    if self.depth == 0 && new_line && !block_token {
+                        break;
+                    }
+                    last_row = Some(token.span.hi.row);
+                }
+            };
+            let _ = self.next();
+        }
+
+        if matches!(self.current_token, Some(Err(_))) {
+            self.current_token = None;
+        }
+    }
+
     fn int(&mut self) -> Result<Literal, ParserError> {
-        let token = expect!(self, TokenKind::Integer(_i));
+        let token = expect!(self, TokenKind::Integer(_i), "integer");
         return Ok(Literal::Integer(token.get_int().unwrap()));
     }
 
     fn identifier(&mut self) -> Result<String, ParserError> {
-        let token = expect!(self, TokenKind::Ident(_i));
+        let token = expect!(self, TokenKind::Ident(_i), "identifier");
         return Ok(token.get_identifier().unwrap());
     }
 
+    // A name is a sequence of identifiers on the same line, joined by a
+    // single space, so that `marine  profile` and `marine profile` name
+    // the same symbol. The name may end with `in`, as in `stand in`, when
+    // no operand follows it on that line; otherwise `in` is the operator.
+    fn name(&mut self) -> Result<String, ParserError> {
+        let mut name = self.identifier()?;
+        loop {
+            let row = self.current_span()?.hi.row;
+            let same_line = self.peek().is_some_and(|x| x.span.lo.row == row);
+            if same_line && peek!(self, TokenKind::Ident(_)) {
+                name.push(' ');
+                name.push_str(&self.identifier()?);
+            } else if same_line && peek!(self, TokenKind::In) && !self.operand_after_in(row) {
+                self.next()?;
+                name.push_str(" in");
+                return Ok(name);
+            } else {
+                return Ok(name);
+            }
+        }
+    }
+
+    // Whether the token after the lookahead `in` starts an operand on the
+    // given row.
+    fn operand_after_in(&mut self, row: usize) -> bool {
+        return match self.peek_second() {
+            Some(token) if token.span.lo.row == row => matches!(
+                token.kind,
+                TokenKind::Ident(_)
+                    | TokenKind::Integer(_)
+                    | TokenKind::Float(_)
+                    | TokenKind::Str(_)
+                    | TokenKind::LParen
+                    | TokenKind::LBracket
+                    | TokenKind::Minus
+                    | TokenKind::Plus
+            ),
+            _ => false,
+        };
+    }
+
+    fn with_line_elements<T>(
+        &mut self,
+        line_elements: bool,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        let previous = std::mem::replace(&mut self.line_elements, line_elements);
+        let result = parse(self);
+        self.line_elements = previous;
+        return result;
+    }
+
+    // Whether the lookahead continues the expression being parsed, which it
+    // does not when it starts the next element of an indented list.
+    fn continues_line(&self) -> Result<bool, ParserError> {
+        return match self.peek() {
+            Some(token) if self.line_elements => {
+                Ok(token.span.lo.row <= self.current_span()?.hi.row)
+            }
+            _ => Ok(true),
+        };
+    }
+
+    // Parses the comma separated arguments of a call, up to and including
+    // the closing parenthesis.
+    fn arguments(&mut self) -> Result<Vec<Expression>, ParserError> {
+        return self.with_line_elements(false, |parser| {
+            let mut args = Vec::new();
+            if accept!(parser, TokenKind::RParen) {
+                return Ok(args);
+            }
+            args.push(parser.expression()?);
+            while accept!(parser, TokenKind::Comma) {
+                args.push(parser.expression()?);
+            }
+            expect!(parser, TokenKind::RParen, "`,` or `)`");
+            return Ok(args);
+        });
+    }
+
+    // Parses the elements of a list up to and including the closing bracket.
+    // Elements are separated by commas, or by new lines when the list opens
+    // an indented block:
+    //
+    // heavy weapons = [
+    //     heavy bolter
+    //     lascannon
+    // ]
+    fn list_elements(&mut self) -> Result<Vec<Expression>, ParserError> {
+        if accept!(self, TokenKind::Indent) {
+            return self.with_line_elements(true, Parser::indented_elements);
+        }
+
+        return self.with_line_elements(false, |parser| {
+            let mut elements = Vec::new();
+            while !accept!(parser, TokenKind::RBracket) {
+                elements.push(parser.expression()?);
+                if !accept!(parser, TokenKind::Comma) {
+                    expect!(parser, TokenKind::RBracket, "`,` or `]`");
+                    break;
+                }
+            }
+            return Ok(elements);
+        });
+    }
+
+    fn indented_elements(&mut self) -> Result<Vec<Expression>, ParserError> {
+        let mut elements = Vec::new();
+        loop {
+            if accept!(self, TokenKind::Deindent) {
+                expect!(self, TokenKind::RBracket, "`]`");
+                return Ok(elements);
+            }
+            if accept!(self, TokenKind::RBracket) {
+                expect!(self, TokenKind::Deindent, "end of block");
+                return Ok(elements);
+            }
+            elements.push(self.expression()?);
+            let closing = peek!(self, TokenKind::Deindent | TokenKind::RBracket);
+            if !accept!(self, TokenKind::Comma) && !closing && self.continues_line()? {
+                return Err(self.unexpected(&["`,`", "new line"]));
+            }
+        }
+    }
+
     fn primary_expression(&mut self) -> Result<Expression, ParserError> {
-        let start = self.current_span()?;
-        if accept!(self, TokenKind::Ident(_)) {
-            let lhs = self.current().unwrap().get_identifier().unwrap();
+        let start = self.next_span();
+        if peek!(self, TokenKind::Ident(_)) {
+            let lhs = self.name()?;
+            if self.continues_line()? && accept!(self, TokenKind::LParen) {
+                let args = self.arguments()?;
+                return Ok(Expression::call(
+                    lhs,
+                    args,
+                    start.merge(&self.current_span()?),
+                ));
+            }
             return Ok(Expression::ident(lhs, start.merge(&self.current_span()?)));
         }
-        if accept!(self, TokenKind::Integer(_)) {
-            let lhs = self.current().unwrap().get_int().unwrap();
-            return Ok(Expression::int(lhs, start.merge(&self.current_span()?)));
+        if accept!(self, TokenKind::Str(_)) {
+            let lhs = self.current().unwrap().get_str().unwrap();
+            return Ok(Expression::str(lhs, start.merge(&self.current_span()?)));
+        }
+        if accept!(self, TokenKind::Float(_)) {
+            let lhs = self.current().unwrap().get_float().unwrap();
+            return Ok(Expression::float(lhs, start.merge(&self.current_span()?)));
+        }
+        if peek!(self, TokenKind::Integer(_)) {
+            let lhs = self.int()?;
+            return Ok(Expression::new_lit(lhs, start.merge(&self.current_span()?)));
+        }
+        if accept!(self, TokenKind::LBracket) {
+            let elements = self.list_elements()?;
+            return Ok(Expression::list(
+                elements,
+                start.merge(&self.current_span()?),
+            ));
         }
         if accept!(self, TokenKind::LParen) {
-            let mut lhs = self.expression()?;
-            expect!(self, TokenKind::RParen);
+            let mut lhs = self.with_line_elements(false, Parser::expression)?;
+            expect!(self, TokenKind::RParen, "`)`");
             lhs.set_span(start.merge(&self.current_span()?));
             return Ok(lhs);
         }
 
-        return self.primary_expression();
+        return Err(self.unexpected(&["expression"]));
+    }
+
+    fn postfix_expression(&mut self) -> Result<Expression, ParserError> {
+        let start = self.next_span();
+        let mut lhs = self.primary_expression()?;
+        while self.continues_line()? && accept!(self, TokenKind::Dot) {
+            let member = self.name()?;
+            lhs = Expression::member(lhs, member, start.merge(&self.current_span()?));
+        }
+        return Ok(lhs);
     }
 
     fn unary_expression(&mut self) -> Result<Expression, ParserError> {
-        let start = self.current_span()?;
+        let start = self.next_span();
         if accept!(self, TokenKind::Minus) {
             let lhs = self.unary_expression()?;
-            return Ok(Expression::not(lhs, start.merge(&self.current_span()?)));
+            return Ok(Expression::neg(lhs, start.merge(&self.current_span()?)));
         } else if accept!(self, TokenKind::Plus) {
             let lhs = self.unary_expression()?;
             return Ok(lhs);
         }
 
-        return self.primary_expression();
+        return self.postfix_expression();
     }
 
-    fn multiplicative_expression(&mut self) -> Result<Expression, ParserError> {
-        let start = self.current_span()?;
-        let lhs = self.unary_expression()?;
-        if accept!(self, TokenKind::Star) {
-            let rhs = self.multiplicative_expression()?;
-            return Ok(Expression::mult(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
-        } else if accept!(self, TokenKind::Slash) {
-            let rhs = self.multiplicative_expression()?;
-            return Ok(Expression::div(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
+    // Parses a left associative chain of operands joined by the operators
+    // of a single precedence level, so `10 - 3 - 2` is `(10 - 3) - 2`.
+    fn binary_level(
+        &mut self,
+        operand: fn(&mut Self) -> Result<Expression, ParserError>,
+        operators: &[(TokenKind, BinaryExpressionKind)],
+    ) -> Result<Expression, ParserError> {
+        let start = self.next_span();
+        let mut lhs = operand(self)?;
+        loop {
+            if !self.continues_line()? {
+                return Ok(lhs);
+            }
+            let next = self.peek().map(|x| x.kind);
+            let kind = match operators.iter().find(|x| Some(&x.0) == next.as_ref()) {
+                Some((_, kind)) => kind.clone(),
+                None => return Ok(lhs),
+            };
+            self.next()?;
+            let rhs = operand(self)?;
+            lhs = Expression::new_bin(kind, lhs, rhs, start.merge(&self.current_span()?));
         }
+    }
 
-        return Ok(lhs);
+    fn multiplicative_expression(&mut self) -> Result<Expression, ParserError> {
+        return self.binary_level(
+            Parser::unary_expression,
+            &[
+                (TokenKind::Star, BinaryExpressionKind::Mult),
+                (TokenKind::Slash, BinaryExpressionKind::Div),
+            ],
+        );
     }
 
     fn additive_expression(&mut self) -> Result<Expression, ParserError> {
-        let start = self.current_span()?;
-        let lhs = self.multiplicative_expression()?;
-        if accept!(self, TokenKind::Plus) {
-            let rhs = self.additive_expression()?;
-            return Ok(Expression::add(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
-        } else if accept!(self, TokenKind::Minus) {
-            let rhs = self.additive_expression()?;
-            return Ok(Expression::sub(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
-        }
-
-        return Ok(lhs);
+        return self.binary_level(
+            Parser::multiplicative_expression,
+            &[
+                (TokenKind::Plus, BinaryExpressionKind::Add),
+                (TokenKind::Minus, BinaryExpressionKind::Sub),
+            ],
+        );
     }
 
     fn relational_expression(&mut self) -> Result<Expression, ParserError> {
-        let start = self.current_span()?;
-        let lhs = self.additive_expression()?;
-        if accept!(self, TokenKind::Less) {
-            let rhs = self.relational_expression()?;
-            return Ok(Expression::less(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
-        } else if accept!(self, TokenKind::LessEqual) {
-            let rhs = self.relational_expression()?;
-            return Ok(Expression::less_equal(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
-        } else if accept!(self, TokenKind::Greater) {
-            let rhs = self.relational_expression()?;
-            return Ok(Expression::greater(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
-        } else if accept!(self, TokenKind::GreaterEqual) {
-            let rhs = self.relational_expression()?;
-            return Ok(Expression::greater_equal(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
-        }
-        return Ok(lhs);
+        return self.binary_level(
+            Parser::additive_expression,
+            &[
+                (TokenKind::Less, BinaryExpressionKind::Less),
+                (TokenKind::LessEqual, BinaryExpressionKind::LessEqual),
+                (TokenKind::Greater, BinaryExpressionKind::Greater),
+                (TokenKind::GreaterEqual, BinaryExpressionKind::GreaterEqual),
+                (TokenKind::In, BinaryExpressionKind::In),
+            ],
+        );
     }
 
     fn equal_expression(&mut self) -> Result<Expression, ParserError> {
-        let start = self.current_span()?;
-        let lhs = self.relational_expression()?;
-        if accept!(self, TokenKind::Equals) {
-            let rhs = self.equal_expression()?;
-            return Ok(Expression::equal(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
-        } else if accept!(self, TokenKind::Different) {
-            let rhs = self.equal_expression()?;
-            return Ok(Expression::different(
-                lhs,
-                rhs,
-                start.merge(&self.current_span()?),
-            ));
+        return self.binary_level(
+            Parser::relational_expression,
+            &[
+                (TokenKind::Equals, BinaryExpressionKind::Equal),
+                (TokenKind::Different, BinaryExpressionKind::Different),
+            ],
+        );
+    }
+
+    // `not` binds looser than comparisons, `not a == b` negates the whole
+    // comparison.
+    fn not_expression(&mut self) -> Result<Expression, ParserError> {
+        let start = self.next_span();
+        if accept!(self, TokenKind::Not) {
+            let lhs = self.not_expression()?;
+            return Ok(Expression::not(lhs, start.merge(&self.current_span()?)));
         }
 
-        return Ok(lhs);
+        return self.equal_expression();
     }
 
     fn and_expression(&mut self) -> Result<Expression, ParserError> {
-        let start = self.current_span()?;
-        let lhs = self.equal_expression()?;
-        if !accept!(self, TokenKind::And) {
-            return Ok(lhs);
-        }
-        let rhs = self.and_expression()?;
-        return Ok(Expression::and(
-            lhs,
-            rhs,
-            start.merge(&self.current_span()?),
-        ));
+        return self.binary_level(
+            Parser::not_expression,
+            &[(TokenKind::And, BinaryExpressionKind::And)],
+        );
     }
 
     fn expression(&mut self) -> Result<Expression, ParserError> {
-        let start = self.current_span()?;
-        let lhs = self.and_expression()?;
-        if !accept!(self, TokenKind::Or) {
-            return Ok(lhs);
-        }
-        let rhs = self.expression()?;
-        return Ok(Expression::or(lhs, rhs, start.merge(&self.current_span()?)));
+        return self.binary_level(
+            Parser::and_expression,
+            &[(TokenKind::Or, BinaryExpressionKind::Or)],
+        );
     }
 
     fn constant_body(&mut self) -> Result<ConstantDeclaration, ParserError> {
-        let start = self.current_span()?;
-        let name = self.identifier()?;
+        let start = self.next_span();
+        let name = self.name()?;
 
         if accept!(self, TokenKind::Assign) {
             let initializer = self.expression()?;
@@ -266,7 +494,9 @@ impl<'a> Parser<'a> {
             ));
         }
 
-        expect!(self, TokenKind::Indent);
+        if !accept!(self, TokenKind::Indent) {
+            return Err(self.unexpected(&["`=`", "indented block"]));
+        }
 
         let mut children = Vec::new();
         while !accept!(self, TokenKind::Deindent) {
@@ -279,24 +509,50 @@ impl<'a> Parser<'a> {
     }
 
     pub fn constant_declaration(&mut self) -> Result<ConstantDeclaration, ParserError> {
-        let start = self.current_span()?;
-        expect!(self, TokenKind::Const);
+        let start = self.next_span();
+        expect!(self, TokenKind::Const, "`const`");
         let mut constant = self.constant_body()?;
         constant.set_span(start.merge(&self.current_span()?));
         return Ok(constant);
     }
 
+    pub fn requirement_declaration(&mut self) -> Result<RequirementDeclaration, ParserError> {
+        let start = self.next_span();
+        expect!(self, TokenKind::Require, "`require`");
+        let indented = accept!(self, TokenKind::Indent);
+        let condition = self.expression()?;
+        let message = expect!(self, TokenKind::Str(_), "string");
+        if indented {
+            expect!(self, TokenKind::Deindent, "end of block");
+        }
+        let mut requirement = RequirementDeclaration::new(
+            condition,
+            message.get_str().unwrap(),
+            start.merge(&self.current_span()?),
+        );
+        requirement.set_message_span(message.span);
+        return Ok(requirement);
+    }
+
     pub fn option_field_declaration(&mut self) -> Result<OptionField, ParserError> {
-        if self.peek().map_or(false, |x| x.kind == TokenKind::Const) {
+        if peek!(self, TokenKind::Require) {
+            let decl = self.requirement_declaration()?;
+            return Ok(OptionField::Require(decl));
+        }
+        if peek!(self, TokenKind::Const) {
             let decl = self.constant_declaration()?;
             return Ok(OptionField::Const(decl));
         }
+        if peek!(self, TokenKind::Alt) {
+            let decl = self.alternative_declaration()?;
+            return Ok(OptionField::Alt(decl));
+        }
         return Ok(OptionField::SubOption(self.option_declaration_body()?));
     }
 
     pub fn option_declaration_body(&mut self) -> Result<OptionDeclaration, ParserError> {
-        let start = self.current_span()?;
-        let name = self.identifier()?;
+        let start = self.next_span();
+        let name = self.name()?;
 
         if !accept!(self, TokenKind::Indent) {
             return Ok(OptionDeclaration::new(
@@ -316,16 +572,16 @@ impl<'a> Parser<'a> {
     }
 
     pub fn option_declaration(&mut self) -> Result<OptionDeclaration, ParserError> {
-        let start = self.current_span()?;
-        expect!(self, TokenKind::Opt);
+        let start = self.next_span();
+        expect!(self, TokenKind::Opt, "`opt`");
         let mut declaration = self.option_declaration_body()?;
         declaration.set_span(start.merge(&self.current_span()?));
         return Ok(declaration);
     }
 
     pub fn alternative_declaration_body(&mut self) -> Result<AlternativeDeclaration, ParserError> {
-        let start = self.current_span()?;
-        let name = self.identifier()?;
+        let start = self.next_span();
+        let name = self.name()?;
 
         if !accept!(self, TokenKind::Indent) {
             return Ok(AlternativeDeclaration::new(
@@ -345,23 +601,98 @@ impl<'a> Parser<'a> {
     }
 
     pub fn alternative_declaration(&mut self) -> Result<AlternativeDeclaration, ParserError> {
-        let start = self.current_span()?;
-        expect!(self, TokenKind::Alt);
+        let start = self.next_span();
+        expect!(self, TokenKind::Alt, "`alt`");
         let mut declaration = self.alternative_declaration_body()?;
         declaration.set_span(start.merge(&self.current_span()?));
         return Ok(declaration);
     }
 
+    fn bounds(&mut self) -> Result<(i64, i64), ParserError> {
+        let start = self.next_span();
+        let min = expect!(self, TokenKind::Integer(_), "integer")
+            .get_int()
+            .unwrap();
+        if !accept!(self, TokenKind::Minus) {
+            return Ok((min, min));
+        }
+        let max = expect!(self, TokenKind::Integer(_), "integer")
+            .get_int()
+            .unwrap();
+        if max < min {
+            let span = start.merge(&self.current_span()?);
+            return Err(ParserError::new_invalid_bounds(span, min, max));
+        }
+        return Ok((min, max));
+    }
+
+    pub fn entry_field_declaration(&mut self) -> Result<EntryField, ParserError> {
+        let start = self.next_span();
+        let (min, max) = self.bounds()?;
+        let name = self.name()?;
+        let initializer = if accept!(self, TokenKind::Assign) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        let mut field = EntryField::new(name, min, max, initializer, Span::new());
+        if accept!(self, TokenKind::Indent) {
+            let mut children = Vec::new();
+            while !accept!(self, TokenKind::Deindent) {
+                children.push(self.constant_body()?);
+            }
+            *field.get_fields_mut() = children;
+        }
+        field.set_span(start.merge(&self.current_span()?));
+        return Ok(field);
+    }
+
+    pub fn entry_declaration(&mut self) -> Result<EntryDeclaration, ParserError> {
+        let start = self.next_span();
+        expect!(self, TokenKind::Entry, "`entry`");
+        let name = self.name()?;
+
+        let mut decl = EntryDeclaration::new(name, Span::new());
+        if accept!(self, TokenKind::Indent) {
+            let mut constants = Vec::new();
+            let mut fields = Vec::new();
+            let mut requirements = Vec::new();
+            while !accept!(self, TokenKind::Deindent) {
+                if peek!(self, TokenKind::Require) {
+                    requirements.push(self.requirement_declaration()?);
+                } else if peek!(self, TokenKind::Const) {
+                    constants.push(self.constant_declaration()?);
+                } else {
+                    fields.push(self.entry_field_declaration()?);
+                }
+            }
+            *decl.get_constants_mut() = constants;
+            *decl.get_fields_mut() = fields;
+            *decl.get_requirements_mut() = requirements;
+        }
+        decl.set_span(start.merge(&self.current_span()?));
+        return Ok(decl);
+    }
+
     pub fn choise_declaration(&mut self) -> Result<ChoiseDeclaration, ParserError> {
-        let start = self.current_span()?;
-        expect!(self, TokenKind::Ident(_));
-        let symbol = self.identifier()?;
+        let start = self.next_span();
+        let quantity = if peek!(self, TokenKind::Integer(_)) {
+            expect!(self, TokenKind::Integer(_), "integer").get_int()
+        } else {
+            None
+        };
+        if !peek!(self, TokenKind::Ident(_)) {
+            return Err(self.unexpected(&["selection name"]));
+        }
+        let symbol = self.name()?;
         let exp = if accept!(self, TokenKind::Assign) {
             Some(self.expression()?)
         } else {
             None
         };
         let mut decl = ChoiseDeclaration::new(symbol, exp, start.merge(&self.current_span()?));
+        decl.set_quantity(quantity);
         if !accept!(self, TokenKind::Indent) {
             return Ok(decl);
         }
@@ -387,23 +718,82 @@ impl<'a> Parser<'a> {
             let decl = self.alternative_declaration()?;
             return Ok(Declaration::Alt(decl));
         }
-        return Err(ParserError::new_unexpected_token(self.current()?));
+        if peek!(self, TokenKind::Entry) {
+            let decl = self.entry_declaration()?;
+            return Ok(Declaration::Entry(decl));
+        }
+        if peek!(self, TokenKind::Require) {
+            let decl = self.requirement_declaration()?;
+            return Ok(Declaration::Require(decl));
+        }
+        if peek!(self, TokenKind::Integer(_) | TokenKind::Ident(_)) {
+            let decl = self.choise_declaration()?;
+            return Ok(Declaration::Choice(decl));
+        }
+        return Err(self.unexpected(&["declaration", "selection"]));
     }
 
     pub fn document(&mut self) -> Result<Document, ParserError> {
         let mut document = Document::new();
 
-        while self.current_token.is_some() {
+        while !self.at_end()? {
             document.entries.push(self.declaration()?);
         }
 
         return Ok(document);
     }
 
-    pub fn parse(s: &String) -> Result<Document, ParserError> {
-        let mut parser = Parser::new(&s)?;
+    pub fn document_with_recovery(&mut self) -> (Document, Vec<ParserError>) {
+        let mut document = Document::new();
+        let mut errors = Vec::new();
+
+        loop {
+            match self.at_end() {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(err) => {
+                    errors.push(err);
+                    self.synchronize(self.consumed, &mut Vec::new());
+                    continue;
+                }
+            };
+
+            let consumed_before = self.consumed;
+            match self.declaration() {
+                Ok(decl) => document.entries.push(decl),
+                Err(err) => {
+                    errors.push(err);
+                    self.synchronize(consumed_before, &mut errors);
+                }
+            };
+        }
+
+        return (document, errors);
+    }
+
+    pub fn parse(s: &str) -> Result<Document, ParserError> {
+        let mut parser = Parser::new(s)?;
         return parser.document();
     }
+
+    pub fn parse_expression(s: &str) -> Result<Expression, ParserError> {
+        let mut parser = Parser::new(s)?;
+        let expression = parser.expression()?;
+        if !parser.at_end()? {
+            return Err(parser.unexpected(&["end of expression"]));
+        }
+        return Ok(expression);
+    }
+
+    // Parses as much of the source as possible, skipping every top level
+    // declaration that contains an error.
+    pub fn parse_with_recovery(s: &str) -> (Document, Vec<ParserError>) {
+        let mut parser = match Parser::new(s) {
+            Ok(parser) => parser,
+            Err(err) => return (Document::new(), vec![err]),
+        };
+        return parser.document_with_recovery();
+    }
 }
 
 #[cfg(test)]
@@ -443,6 +833,48 @@ mod tests {
         assert_eq!(expression.span().hi.column, 7);
     }
 
+    #[test]
+    fn member_expression() {
+        let mut parser = Parser::new("marine profile.weapon skill.value + 1").unwrap();
+        let expression = parser.expression().unwrap();
+        let member = expression.left().unwrap();
+        assert_eq!(member.member_name().unwrap(), "value");
+        assert_eq!(member.span().hi.column, 33);
+        let inner = member.left().unwrap();
+        assert_eq!(inner.member_name().unwrap(), "weapon skill");
+        assert_eq!(
+            *inner.left().unwrap().literal().unwrap(),
+            Literal::Indent("marine profile".to_owned())
+        );
+        assert_eq!(
+            expression.to_string(),
+            "marine profile.weapon skill.value + 1"
+        );
+    }
+
+    #[test]
+    fn call_expression() {
+        let mut parser = Parser::new("count(tactical marines) + max(a, 2 * b, c.d)").unwrap();
+        let expression = parser.expression().unwrap();
+        let count = expression.left().unwrap();
+        assert_eq!(count.arity(), 1);
+        assert_eq!(
+            *count.arguments().unwrap()[0].literal().unwrap(),
+            Literal::Indent("tactical marines".to_owned())
+        );
+        assert_eq!(count.span().hi.column, 23);
+        assert_eq!(expression.right().unwrap().arity(), 3);
+        assert_eq!(
+            expression.to_string(),
+            "count(tactical marines) + max(a, 2 * b, c.d)"
+        );
+        assert_eq!(
+            Parser::new("any()").unwrap().expression().unwrap().arity(),
+            0
+        );
+        assert!(Parser::new("min(1 2)").unwrap().expression().is_err());
+    }
+
     #[test]
     fn equal_expression() {
         let mut parser = Parser::new("43 == 53").unwrap();
@@ -485,6 +917,142 @@ mod tests {
         assert_eq!(expression.span().hi.column, 9);
     }
 
+    #[test]
+    fn not_expression() {
+        let mut parser = Parser::new("not a == b and !c != -d").unwrap();
+        let expression = parser.expression().unwrap();
+        assert_eq!(
+            *expression.binary_kind().unwrap(),
+            BinaryExpressionKind::And
+        );
+        let lhs = expression.left().unwrap();
+        assert_eq!(*lhs.unary_kind().unwrap(), UnaryExpressionKind::Not);
+        assert!(lhs.left().unwrap().is_binary());
+        assert_eq!(lhs.span().lo.column, 0);
+        assert_eq!(lhs.span().hi.column, 10);
+        let rhs = expression.right().unwrap();
+        assert_eq!(*rhs.unary_kind().unwrap(), UnaryExpressionKind::Not);
+        let comparison = rhs.left().unwrap();
+        assert_eq!(
+            *comparison.right().unwrap().unary_kind().unwrap(),
+            UnaryExpressionKind::Neg
+        );
+        assert_eq!(expression.to_string(), "not a == b and not c != -d");
+    }
+
+    // Renders every binary expression in parentheses, to check how the
+    // operators were grouped.
+    fn parenthesize(expression: &Expression) -> String {
+        if let Some(kind) = expression.binary_kind() {
+            let lhs = parenthesize(expression.left().unwrap());
+            let rhs = parenthesize(expression.right().unwrap());
+            return format!("({} {} {})", lhs, kind.symbol(), rhs);
+        }
+        let operand = expression.left().map(parenthesize);
+        if let Some(kind) = expression.unary_kind() {
+            let symbol = match kind {
+                UnaryExpressionKind::Neg => "-",
+                UnaryExpressionKind::Not => "not ",
+            };
+            return format!("{}{}", symbol, operand.unwrap());
+        }
+        if let Some(member) = expression.member_name() {
+            return format!("{}.{}", operand.unwrap(), member);
+        }
+        return expression.to_string();
+    }
+
+    fn parenthesized(source: &str) -> String {
+        return parenthesize(&Parser::parse_expression(source).unwrap());
+    }
+
+    #[test]
+    fn left_associative_operators() {
+        let cases = [
+            ("10 - 3 - 2", "((10 - 3) - 2)"),
+            ("1 + 2 - 3 + 4", "(((1 + 2) - 3) + 4)"),
+            ("100 / 10 / 5", "((100 / 10) / 5)"),
+            ("2 * 3 / 4 * 5", "(((2 * 3) / 4) * 5)"),
+            ("a < b < c", "((a < b) < c)"),
+            ("a <= b >= c > d", "(((a <= b) >= c) > d)"),
+            ("a == b != c == d", "(((a == b) != c) == d)"),
+            ("a and b and c", "((a and b) and c)"),
+            ("a or b or c", "((a or b) or c)"),
+        ];
+        for (source, expected) in cases.iter() {
+            assert_eq!(parenthesized(source), *expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn operator_precedence() {
+        let cases = [
+            ("1 + 2 * 3", "(1 + (2 * 3))"),
+            ("1 * 2 + 3 / 4 - 5", "(((1 * 2) + (3 / 4)) - 5)"),
+            ("-a * b", "(-a * b)"),
+            ("a + b < c - d", "((a + b) < (c - d))"),
+            ("a < b == c >= d", "((a < b) == (c >= d))"),
+            ("a == b and c != d", "((a == b) and (c != d))"),
+            ("not a and b", "(not a and b)"),
+            ("a or b and c or d", "((a or (b and c)) or d)"),
+            ("(1 + 2) * 3", "((1 + 2) * 3)"),
+            ("a.b * f(c) + 1", "((a.b * f(c)) + 1)"),
+        ];
+        for (source, expected) in cases.iter() {
+            assert_eq!(parenthesized(source), *expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn binary_spans() {
+        let expression = Parser::parse_expression("10 - 3 - 2").unwrap();
+        assert_eq!(expression.span().lo.column, 0);
+        assert_eq!(expression.span().hi.column, 10);
+        let lhs = expression.left().unwrap();
+        assert_eq!(lhs.span().lo.column, 0);
+        assert_eq!(lhs.span().hi.column, 6);
+        let rhs = expression.right().unwrap();
+        assert_eq!(rhs.span().lo.column, 9);
+    }
+
+    #[test]
+    fn list_literals() {
+        let list = Parser::parse_expression("[1, a + 2, [],]").unwrap();
+        match list.literal() {
+            Some(Literal::List(elements)) => assert_eq!(elements.len(), 3),
+            _ => panic!("expected a list literal"),
+        }
+        assert_eq!(list.to_string(), "[1, a + 2, []]");
+        assert_eq!(list.span().hi.column, 15);
+        assert_eq!(parenthesized("a in [a, b] and b"), "((a in [a, b]) and b)");
+        assert!(Parser::parse_expression("[1 2]").is_err());
+
+        let document = Parser::parse(
+            "const a = [\n heavy bolter\n lascannon, plasma gun\n]\nconst b = [\n 1\n 2]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            document.to_string(),
+            "const a = [heavy bolter, lascannon, plasma gun]\nconst b = [1, 2]\n"
+        );
+    }
+
+    #[test]
+    fn indented_list_elements_end_with_their_line() {
+        let document =
+            Parser::parse("const a = [\n 1\n -2\n]\nconst b = [\n (1\n -2), f(1,\n 2)\n]\n")
+                .unwrap();
+        assert_eq!(
+            document.to_string(),
+            "const a = [1, -2]\nconst b = [1 - 2, f(1, 2)]\n"
+        );
+        let error = Parser::parse("const a = [\n 1 2\n]\n").unwrap_err();
+        assert_eq!(
+            error.get_unexpected_token().unwrap().token().span.lo.column,
+            3
+        );
+    }
+
     #[test]
     fn les_expression() {
         let mut parser = Parser::new("43 < 53").unwrap();
@@ -574,4 +1142,226 @@ mod tests {
         let document = parser.document();
         assert!(document.is_ok());
     }
+
+    #[test]
+    fn entry_declaration() {
+        let mut parser =
+            Parser::new("entry squad\n 5-10 marines\n  profile = 4\n 1 sergent = 3\n").unwrap();
+        let maybe_declaration = parser.entry_declaration();
+        assert!(maybe_declaration.is_ok());
+        let declaration = maybe_declaration.unwrap();
+        assert_eq!(declaration.name(), "squad");
+
+        let marines = declaration.get_field(0).unwrap();
+        assert_eq!(marines.name(), "marines");
+        assert_eq!(marines.min(), 5);
+        assert_eq!(marines.max(), 10);
+        assert!(marines.get_initializer().is_none());
+        assert_eq!(marines.get_field(0).unwrap().name(), "profile");
+
+        let sergent = declaration.get_field(1).unwrap();
+        assert_eq!(sergent.min(), 1);
+        assert_eq!(sergent.max(), 1);
+        assert_eq!(
+            *sergent.get_initializer().unwrap().literal().unwrap(),
+            Literal::Integer(3)
+        );
+    }
+
+    #[test]
+    fn entry_document_round_trip() {
+        let source = "entry squad\n 5-10 marines\n  profile = 4\n 0-1 sergent\n\n";
+        let document = Parser::parse(source).unwrap();
+        assert_eq!(document.entries.len(), 1);
+        assert_eq!(format!("{}", document), source);
+    }
+
+    #[test]
+    fn requirement_declaration() {
+        let mut parser = Parser::new("require\n a <= 10\n \"too many models\"\n").unwrap();
+        let maybe_declaration = parser.requirement_declaration();
+        assert!(maybe_declaration.is_ok());
+        let declaration = maybe_declaration.unwrap();
+        assert_eq!(declaration.message(), "too many models");
+        assert_eq!(
+            *declaration.condition().binary_kind().unwrap(),
+            BinaryExpressionKind::LessEqual
+        );
+    }
+
+    #[test]
+    fn requirement_in_entry_and_option() {
+        let source = "entry squad\n 5-10 marines\n require\n  marines <= 10\n  \"max\"\n\nopt weapon\n require\n  1 < 2\n  \"never\"\n";
+        let document = Parser::parse(source).unwrap();
+        assert_eq!(document.entries.len(), 2);
+        match &document.entries[0] {
+            Declaration::Entry(entry) => {
+                assert_eq!(entry.get_fields().len(), 1);
+                assert_eq!(entry.get_requirements()[0].message(), "max");
+            }
+            _ => panic!("expected an entry"),
+        }
+        match &document.entries[1] {
+            Declaration::Opt(opt) => {
+                assert!(matches!(opt.get_field(0), Some(OptionField::Require(_))));
+            }
+            _ => panic!("expected an option"),
+        }
+    }
+
+    #[test]
+    fn string_expression_round_trip() {
+        let mut parser = Parser::new("\"a \\\"b\\\" \\\\ c\\n\"").unwrap();
+        let expression = parser.expression().unwrap();
+        assert_eq!(
+            *expression.literal().unwrap(),
+            Literal::Str("a \"b\" \\ c\n".to_owned())
+        );
+        let source = "require\n x\n \"a \\\"quoted\\\" message\"\n";
+        let document = Parser::parse(source).unwrap();
+        assert_eq!(format!("{}", document), source);
+    }
+
+    #[test]
+    fn float_expression_round_trip() {
+        let mut parser = Parser::new("3.5 * 2").unwrap();
+        let expression = parser.expression().unwrap();
+        assert_eq!(
+            *expression.left().unwrap().literal().unwrap(),
+            Literal::Float(3.5)
+        );
+        let source = "const cost = 1.0\n";
+        let document = Parser::parse(source).unwrap();
+        assert_eq!(format!("{}", document), source);
+    }
+
+    #[test]
+    fn multi_word_names() {
+        let source = "const marine profile\n WS = 4\n\nopt heavy weapon\n heavy bolter\n las  cannon\n\nconst x = marine profile\n";
+        let document = Parser::parse(source).unwrap();
+        assert_eq!(document.entries[0].name(), "marine profile");
+        match &document.entries[1] {
+            Declaration::Opt(opt) => {
+                assert_eq!(opt.name(), "heavy weapon");
+                assert_eq!(opt.get_fields().len(), 2);
+                match opt.get_field(1).unwrap() {
+                    OptionField::SubOption(sub) => assert_eq!(sub.name(), "las cannon"),
+                    _ => panic!("expected a sub option"),
+                }
+            }
+            _ => panic!("expected an option"),
+        }
+        match &document.entries[2] {
+            Declaration::Const(constant) => assert_eq!(
+                *constant.get_initializer().unwrap().literal().unwrap(),
+                Literal::Indent("marine profile".to_owned())
+            ),
+            _ => panic!("expected a constant"),
+        }
+
+        let mut table = crate::symbol_table::SymbolTable::new();
+        table.insert(&document.entries[0]).unwrap();
+        assert!(table.get("marine   profile").is_some());
+        assert!(table.get("marine").is_none());
+    }
+
+    #[test]
+    fn names_ending_with_in() {
+        let source = "const stand in = 1\nconst a = stand in\nconst b = stand in * 2\nconst c = stand in in [1]\nconst d = a in [stand in]\n";
+        let document = Parser::parse(source).unwrap();
+        assert_eq!(document.entries[0].name(), "stand in");
+        let initializer = |i: usize| match &document.entries[i] {
+            Declaration::Const(constant) => constant.get_initializer().unwrap().clone(),
+            _ => panic!("expected a constant"),
+        };
+        assert_eq!(
+            *initializer(1).literal().unwrap(),
+            Literal::Indent("stand in".to_owned())
+        );
+        assert_eq!(
+            initializer(2).binary_kind(),
+            Some(&BinaryExpressionKind::Mult)
+        );
+        assert_eq!(
+            initializer(3).binary_kind(),
+            Some(&BinaryExpressionKind::In)
+        );
+        assert_eq!(
+            initializer(4).binary_kind(),
+            Some(&BinaryExpressionKind::In)
+        );
+        assert_eq!(format!("{}", document), source);
+    }
+
+    #[test]
+    fn lexical_errors_are_returned() {
+        assert!(Parser::parse("const a = 4 $ 3")
+            .unwrap_err()
+            .is_invalid_character());
+        assert!(Parser::parse("const a = 99999999999999999999")
+            .unwrap_err()
+            .is_integer_overflow());
+        assert!(Parser::parse("require\n a\n \"unterminated\n")
+            .unwrap_err()
+            .is_invalid_character());
+    }
+
+    #[test]
+    fn inverted_bounds_are_rejected() {
+        let error = Parser::parse("entry squad\n 5-2 marines\n").unwrap_err();
+        let bounds = error.get_invalid_bounds().unwrap();
+        assert_eq!((bounds.min, bounds.max), (5, 2));
+        assert_eq!((bounds.span.lo.row, bounds.span.lo.column), (1, 1));
+        assert_eq!(bounds.span.hi.column, 4);
+        assert!(Parser::parse("entry squad\n 2-2 marines\n").is_ok());
+    }
+
+    #[test]
+    fn roster_choices() {
+        let source = "alt heavy weapon\n heavy bolter\n\nentry tactical squad\n 5-10 tactical marines\n 0-1 sergent\n 0-1 heavy weapons = []\n\n#real list\n1 tactical squad\n 9 tactical marines\n 1 sergent\n\n heavy weapons = [\n  heavy bolter\n ]\n\ncaptain\n";
+        let document = Parser::parse(source).unwrap();
+        assert_eq!(document.entries.len(), 4);
+        let squad = match &document.entries[2] {
+            Declaration::Choice(choice) => choice,
+            _ => panic!("expected a selection"),
+        };
+        assert_eq!(squad.quantity(), Some(1));
+        assert_eq!(squad.symbol(), "tactical squad");
+        assert_eq!(squad.span().lo.row, 9);
+        assert_eq!(squad.get_fields().len(), 3);
+        assert_eq!(squad.get_field(0).unwrap().quantity(), Some(9));
+        assert_eq!(squad.get_field(0).unwrap().symbol(), "tactical marines");
+        let weapons = squad.get_field(2).unwrap();
+        assert_eq!(weapons.quantity(), None);
+        assert_eq!(weapons.get_value().unwrap().to_string(), "[heavy bolter]");
+        assert_eq!(document.entries[3].name(), "captain");
+
+        let serialized = squad.to_string();
+        assert_eq!(
+            serialized,
+            "1 tactical squad\n 9 tactical marines\n 1 sergent\n heavy weapons = [heavy bolter]\n\n"
+        );
+        let reparsed = Parser::parse(&serialized).unwrap();
+        assert_eq!(reparsed.entries[0].to_string(), serialized);
+        assert!(Parser::parse("3 = 4\n").is_err());
+    }
+
+    #[test]
+    fn recovery_collects_every_error() {
+        let source = "const a = 1\nconst b = = 2\nconst c = 3\nopt d\n e\n  f = \n g\nconst h $ 4\nconst i = 5\n";
+        let (document, errors) = Parser::parse_with_recovery(source);
+        let names: Vec<String> = document.entries.iter().map(|x| x.name()).collect();
+        assert_eq!(names, vec!["a", "c", "i"]);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].span().unwrap().lo.row, 1);
+        assert_eq!(errors[1].span().unwrap().lo.row, 5);
+        assert!(errors[2].is_invalid_character());
+    }
+
+    #[test]
+    fn recovery_skips_stray_tokens() {
+        let (document, errors) = Parser::parse_with_recovery(") ) )\nconst a = 1\n");
+        assert_eq!(document.entries.len(), 1);
+        assert_eq!(errors.len(), 1);
+    }
 }
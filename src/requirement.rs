@@ -0,0 +1,88 @@
+use crate::error::*;
+use crate::expression::*;
+use crate::serialization::*;
+use crate::symbol_table::*;
+use crate::token::Span;
+use crate::types::*;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequirementDeclaration {
+    condition: Expression,
+    message: String,
+    span: Span,
+    message_span: Span,
+}
+
+impl Serializable for RequirementDeclaration {
+    fn serialize(
+        &self,
+        f: &mut Formatter,
+        ctx: &SerializationContext,
+    ) -> Result<(), ::std::fmt::Error> {
+        indent(f, ctx.indent)?;
+        writeln!(f, "require")?;
+        indent(f, ctx.indented().indent)?;
+        self.condition.serialize(f, ctx)?;
+        writeln!(f)?;
+        indent(f, ctx.indented().indent)?;
+        writeln!(f, "\"{}\"", escape(&self.message))?;
+        return Ok(());
+    }
+}
+
+impl Display for RequirementDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return self.serialize(f, &SerializationContext::new());
+    }
+}
+
+impl RequirementDeclaration {
+    pub fn new(condition: Expression, message: String, span: Span) -> RequirementDeclaration {
+        return RequirementDeclaration {
+            condition,
+            message,
+            span,
+            message_span: span,
+        };
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn set_message_span(&mut self, span: Span) {
+        self.message_span = span;
+    }
+
+    // The string token of the message, the span of the requirement ends
+    // with the block it is in.
+    pub fn message_span(&self) -> Span {
+        return self.message_span;
+    }
+
+    pub fn span(&self) -> Span {
+        return self.span;
+    }
+
+    pub fn condition(&self) -> &Expression {
+        return &self.condition;
+    }
+
+    pub fn message(&self) -> &String {
+        return &self.message;
+    }
+
+    pub fn type_check(&self, table: &SymbolTable) -> Result<(), ParserError> {
+        let condition = self.condition.type_check(table)?;
+        if condition != Type::Bool {
+            return Err(ParserError::new_type_mismatch(
+                self.condition.span(),
+                "bool requirement".to_owned(),
+                condition.to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -7,6 +7,19 @@ pub fn indent(f: &mut std::fmt::Formatter, indent: usize) -> Result<(), std::fmt
     return Ok(());
 }
 
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum EmissionKind {
     Non,
@@ -20,6 +33,12 @@ pub struct SerializationContext {
     pub emission_kind: EmissionKind,
 }
 
+impl Default for SerializationContext {
+    fn default() -> Self {
+        return SerializationContext::new();
+    }
+}
+
 impl SerializationContext {
     pub fn new() -> SerializationContext {
         SerializationContext {
@@ -30,7 +49,7 @@ impl SerializationContext {
 
     pub fn indented(&self) -> SerializationContext {
         let mut new_context = self.clone();
-        new_context.indent = new_context.indent + 1;
+        new_context.indent += 1;
         return new_context;
     }
 
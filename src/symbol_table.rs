@@ -1,46 +1,263 @@
+use crate::alternative::*;
+use crate::constant::*;
 use crate::declaration::*;
+use crate::entry::*;
+use crate::error::*;
+use crate::option::*;
+use crate::token::Span;
+use crate::types::Type;
+use crate::value::Value;
+use std::cell::RefCell;
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, PartialEq)]
+pub fn normalize_name(name: &str) -> String {
+    return name.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symbol<'a> {
+    Const(&'a ConstantDeclaration),
+    Opt(&'a OptionDeclaration),
+    Alt(&'a AlternativeDeclaration),
+    Entry(&'a EntryDeclaration),
+    Field(&'a EntryField),
+    // A constant whose initializer is being type checked, finding it again
+    // while resolving that initializer means the definition is cyclic.
+    Pending(&'a ConstantDeclaration),
+}
+
+impl<'a> Symbol<'a> {
+    pub fn span(&self) -> Span {
+        return match self {
+            Symbol::Const(decl) => decl.span(),
+            Symbol::Opt(decl) => decl.span(),
+            Symbol::Alt(decl) => decl.span(),
+            Symbol::Entry(decl) => decl.span(),
+            Symbol::Field(decl) => decl.span(),
+            Symbol::Pending(decl) => decl.span(),
+        };
+    }
+}
+
+// The type or value of a top level declaration. Finding a declaration
+// that is still in progress means its definition is cyclic.
+#[derive(Debug, Clone)]
+enum Memo<T> {
+    InProgress,
+    Done(Result<T, ParserError>),
+}
+
+fn memoize<T: Clone>(
+    memos: &RefCell<HashMap<String, Memo<T>>>,
+    name: &str,
+    span: Span,
+    compute: impl FnOnce() -> Result<T, ParserError>,
+) -> Result<T, ParserError> {
+    let name = normalize_name(name);
+    match memos.borrow().get(&name) {
+        Some(Memo::InProgress) => return Err(ParserError::new_cyclic_definition(span, name)),
+        Some(Memo::Done(result)) => return result.clone(),
+        None => {}
+    }
+    memos.borrow_mut().insert(name.clone(), Memo::InProgress);
+    let result = compute();
+    memos.borrow_mut().insert(name, Memo::Done(result.clone()));
+    return result;
+}
+
+// Warnings are only stored in the root table, child tables forward them
+// to their parent. Bindings give a name a value during evaluation, such as
+// the number of models selected for an entry field while validating a
+// roster, without changing the declaration it resolves to. The types and
+// values of top level declarations are computed once and kept in the root
+// table, so that a constant referred to many times is not evaluated again
+// every time.
+#[derive(Debug, Clone)]
 pub struct SymbolTable<'a> {
-    symbols: HashMap<String, &'a Declaration>,
+    symbols: HashMap<String, Symbol<'a>>,
+    bindings: HashMap<String, Value>,
     parent: Option<&'a SymbolTable<'a>>,
+    warnings: RefCell<Vec<ParserError>>,
+    types: RefCell<HashMap<String, Memo<Type>>>,
+    values: RefCell<HashMap<String, Memo<Value>>>,
+}
+
+impl<'a> Default for SymbolTable<'a> {
+    fn default() -> Self {
+        return SymbolTable::new();
+    }
 }
 
 impl<'a> SymbolTable<'a> {
     pub fn new() -> SymbolTable<'a> {
         return SymbolTable {
             symbols: HashMap::new(),
+            bindings: HashMap::new(),
             parent: None,
+            warnings: RefCell::new(Vec::new()),
+            types: RefCell::new(HashMap::new()),
+            values: RefCell::new(HashMap::new()),
         };
     }
 
     pub fn new_from_parent(parent: &'a SymbolTable) -> SymbolTable<'a> {
         return SymbolTable {
             symbols: HashMap::new(),
+            bindings: HashMap::new(),
             parent: Some(parent),
+            warnings: RefCell::new(Vec::new()),
+            types: RefCell::new(HashMap::new()),
+            values: RefCell::new(HashMap::new()),
         };
     }
 
     pub fn make_child(&'a self) -> SymbolTable<'a> {
         return SymbolTable {
             symbols: HashMap::new(),
+            bindings: HashMap::new(),
             parent: Some(self),
+            warnings: RefCell::new(Vec::new()),
+            types: RefCell::new(HashMap::new()),
+            values: RefCell::new(HashMap::new()),
         };
     }
 
-    pub fn insert(&mut self, dec: &'a Declaration) {
-        self.symbols.insert(dec.name(), dec);
+    pub fn insert(&mut self, dec: &'a Declaration) -> Result<(), ParserError> {
+        let symbol = match dec {
+            Declaration::Const(decl) => Symbol::Const(decl),
+            Declaration::Opt(decl) => Symbol::Opt(decl),
+            Declaration::Alt(decl) => Symbol::Alt(decl),
+            Declaration::Entry(decl) => Symbol::Entry(decl),
+            Declaration::Choice(_) | Declaration::Require(_) => return Ok(()),
+        };
+        return self.insert_symbol(&dec.name(), symbol);
     }
 
-    pub fn get(&self, s: &String) -> Option<&Declaration> {
-        let symb = self.symbols.get(s);
-        if symb.is_some() {
-            return Some(*symb.unwrap());
+    // Fails if the name is already declared in this scope, and records a
+    // warning if it hides a declaration of an outer scope.
+    pub fn insert_symbol(&mut self, name: &str, symbol: Symbol<'a>) -> Result<(), ParserError> {
+        let name = normalize_name(name);
+        if let Some(previous) = self.symbols.get(&name) {
+            return Err(ParserError::new_duplicate_declaration(
+                symbol.span(),
+                previous.span(),
+                name,
+            ));
         }
-        if self.parent.is_none() {
+        if let Some(previous) = self.parent.and_then(|x| x.get(&name)) {
+            self.warn(ParserError::new_shadowed_declaration(
+                symbol.span(),
+                previous.span(),
+                name.clone(),
+            ));
+        }
+        self.symbols.insert(name, symbol);
+        return Ok(());
+    }
+
+    pub fn mark_pending(&mut self, constant: &'a ConstantDeclaration) {
+        self.symbols
+            .insert(normalize_name(constant.name()), Symbol::Pending(constant));
+    }
+
+    pub fn bind(&mut self, name: &str, value: Value) {
+        self.bindings.insert(normalize_name(name), value);
+    }
+
+    // The value bound to a name, unless a declaration of a nearer scope
+    // hides the binding.
+    pub fn get_binding(&self, s: &str) -> Option<&Value> {
+        let name = normalize_name(s);
+        if let Some(value) = self.bindings.get(&name) {
+            return Some(value);
+        }
+        if self.symbols.contains_key(&name) {
             return None;
         }
-        return self.parent.unwrap().get(s);
+        return self.parent?.get_binding(s);
+    }
+
+    pub fn warn(&self, warning: ParserError) {
+        match self.parent {
+            Some(parent) => parent.warn(warning),
+            None => self.warnings.borrow_mut().push(warning),
+        };
+    }
+
+    pub fn take_warnings(&self) -> Vec<ParserError> {
+        return self.warnings.borrow_mut().drain(..).collect();
+    }
+
+    pub fn get(&self, s: &str) -> Option<Symbol<'a>> {
+        if let Some(symb) = self.symbols.get(&normalize_name(s)) {
+            return Some(*symb);
+        }
+        return self.parent?.get(s);
+    }
+
+    pub fn root(&self) -> &SymbolTable<'a> {
+        return match self.parent {
+            Some(parent) => parent.root(),
+            None => self,
+        };
+    }
+
+    // The table a name is declared in, where the names used by its
+    // declaration resolve.
+    pub fn declaring_scope(&self, s: &str) -> Option<&SymbolTable<'a>> {
+        if self.symbols.contains_key(&normalize_name(s)) {
+            return Some(self);
+        }
+        return self.parent?.declaring_scope(s);
+    }
+
+    // Whether nothing is declared in this table.
+    pub fn is_empty(&self) -> bool {
+        return self.symbols.is_empty();
+    }
+
+    // An option of one of the alternatives declared in this table, which
+    // rosters may select by its own name.
+    pub fn alternative_option(&self, s: &str) -> Option<&'a OptionDeclaration> {
+        let name = normalize_name(s);
+        return self.symbols.values().find_map(|symbol| match symbol {
+            Symbol::Alt(alternative) => alternative
+                .get_fields()
+                .iter()
+                .find(|x| normalize_name(x.name()) == name),
+            _ => None,
+        });
+    }
+
+    pub fn is_root(&self) -> bool {
+        return self.parent.is_none();
+    }
+
+    // Whether a name resolves to a declaration of the root table.
+    pub fn is_global(&self, s: &str) -> bool {
+        if self.symbols.contains_key(&normalize_name(s)) {
+            return self.parent.is_none();
+        }
+        return self.parent.is_some_and(|x| x.is_global(s));
+    }
+
+    // The type of a top level declaration, computed the first time it is
+    // needed. Must be called on the root table.
+    pub fn memoized_type(
+        &self,
+        name: &str,
+        span: Span,
+        compute: impl FnOnce() -> Result<Type, ParserError>,
+    ) -> Result<Type, ParserError> {
+        return memoize(&self.types, name, span, compute);
+    }
+
+    pub fn memoized_value(
+        &self,
+        name: &str,
+        span: Span,
+        compute: impl FnOnce() -> Result<Value, ParserError>,
+    ) -> Result<Value, ParserError> {
+        return memoize(&self.values, name, span, compute);
     }
 }
@@ -1,8 +1,10 @@
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
     Ident(String),
+    Str(String),
 
     Integer(i64),
+    Float(f64),
     Equals,
     Different,
 
@@ -16,14 +18,22 @@ pub enum TokenKind {
     Minus,
     Or,
     And,
+    Not,
+    In,
     Star,
     Slash,
     LParen,
     RParen,
+    LBracket,
+    RBracket,
     Semi,
+    Dot,
+    Comma,
 
     Alt,
     Const,
+    Entry,
+    Require,
     Opt,
 
     Whitespace(i64),
@@ -32,6 +42,58 @@ pub enum TokenKind {
     EndLine,
     Indent,
     Deindent,
+
+    InvalidCharacter(String),
+    IntegerOverflow(String),
+    FloatOverflow(String),
+    InvalidEscape(String),
+}
+
+impl TokenKind {
+    pub fn describe(&self) -> String {
+        return match self {
+            TokenKind::Ident(s) => format!("`{}`", s),
+            TokenKind::Str(s) => format!("string \"{}\"", s),
+            TokenKind::Integer(i) => format!("`{}`", i),
+            TokenKind::Float(v) => format!("`{:?}`", v),
+            TokenKind::Equals => "`==`".to_owned(),
+            TokenKind::Different => "`!=`".to_owned(),
+            TokenKind::Less => "`<`".to_owned(),
+            TokenKind::LessEqual => "`<=`".to_owned(),
+            TokenKind::Greater => "`>`".to_owned(),
+            TokenKind::GreaterEqual => "`>=`".to_owned(),
+            TokenKind::Assign => "`=`".to_owned(),
+            TokenKind::Plus => "`+`".to_owned(),
+            TokenKind::Minus => "`-`".to_owned(),
+            TokenKind::Or => "`or`".to_owned(),
+            TokenKind::And => "`and`".to_owned(),
+            TokenKind::Not => "`not`".to_owned(),
+            TokenKind::In => "`in`".to_owned(),
+            TokenKind::Star => "`*`".to_owned(),
+            TokenKind::Slash => "`/`".to_owned(),
+            TokenKind::LParen => "`(`".to_owned(),
+            TokenKind::RParen => "`)`".to_owned(),
+            TokenKind::LBracket => "`[`".to_owned(),
+            TokenKind::RBracket => "`]`".to_owned(),
+            TokenKind::Semi => "`;`".to_owned(),
+            TokenKind::Dot => "`.`".to_owned(),
+            TokenKind::Comma => "`,`".to_owned(),
+            TokenKind::Alt => "`alt`".to_owned(),
+            TokenKind::Const => "`const`".to_owned(),
+            TokenKind::Entry => "`entry`".to_owned(),
+            TokenKind::Require => "`require`".to_owned(),
+            TokenKind::Opt => "`opt`".to_owned(),
+            TokenKind::Whitespace(_) => "whitespace".to_owned(),
+            TokenKind::Comment => "comment".to_owned(),
+            TokenKind::EndLine => "end of line".to_owned(),
+            TokenKind::Indent => "indented block".to_owned(),
+            TokenKind::Deindent => "end of block".to_owned(),
+            TokenKind::InvalidCharacter(s) => format!("`{}`", s),
+            TokenKind::IntegerOverflow(s) => format!("`{}`", s),
+            TokenKind::FloatOverflow(s) => format!("`{}`", s),
+            TokenKind::InvalidEscape(s) => format!("`{}`", s),
+        };
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -46,6 +108,12 @@ pub struct Span {
     pub hi: SourceLocation,
 }
 
+impl Default for Span {
+    fn default() -> Self {
+        return Span::new();
+    }
+}
+
 impl Span {
     pub fn merge(self, other: &Span) -> Span {
         let lo = self.lo;
@@ -75,10 +143,7 @@ impl Token {
     }
 
     pub fn is_whitespace(&self) -> bool {
-        match self.kind {
-            TokenKind::Whitespace(_a) => return true,
-            _ => return false,
-        };
+        return matches!(self.kind, TokenKind::Whitespace(_a));
     }
 
     pub fn try_merge_whitespace(&self, other: &Token) -> Option<Token> {
@@ -95,7 +160,7 @@ impl Token {
         {
             return Some(Token {
                 kind: TokenKind::Whitespace(a + b),
-                span: s1.merge(&s2),
+                span: s1.merge(s2),
             });
         }
         return None;
@@ -108,8 +173,16 @@ impl Token {
         }
     }
 
-    pub fn get_float(&self) -> Option<i64> {
+    pub fn get_str(&self) -> Option<String> {
+        match self.kind.clone() {
+            TokenKind::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn get_float(&self) -> Option<f64> {
         match self.kind {
+            TokenKind::Float(a) => Some(a),
             _ => None,
         }
     }
@@ -0,0 +1,68 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Integer,
+    Float,
+    Bool,
+    Str,
+    Record(String),
+    Selection,
+    // The element type of an empty list is not known.
+    List(Option<Box<Type>>),
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return match self {
+            Type::Integer => write!(f, "integer"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::Str => write!(f, "string"),
+            Type::Record(name) => write!(f, "record `{}`", name),
+            Type::Selection => write!(f, "selection"),
+            Type::List(None) => write!(f, "empty list"),
+            Type::List(Some(element)) => write!(f, "list of {}", element),
+        };
+    }
+}
+
+impl Type {
+    pub fn is_numeric(&self) -> bool {
+        return matches!(self, Type::Integer | Type::Float);
+    }
+
+    // Integers and floats compare with each other, every other type only
+    // compares with itself.
+    pub fn is_comparable_with(&self, other: &Type) -> bool {
+        return self.unify(other).is_some();
+    }
+
+    // The type that can hold values of both types, used for the elements
+    // of a list: `[1, 2.5]` is a list of floats.
+    pub fn unify(&self, other: &Type) -> Option<Type> {
+        if self.is_numeric() && other.is_numeric() {
+            return Some(self.numeric_result(other));
+        }
+        return match (self, other) {
+            (Type::List(None), Type::List(_)) => Some(other.clone()),
+            (Type::List(_), Type::List(None)) => Some(self.clone()),
+            (Type::List(Some(lhs)), Type::List(Some(rhs))) => {
+                Some(Type::List(Some(Box::new(lhs.unify(rhs)?))))
+            }
+            _ if self == other => Some(self.clone()),
+            _ => None,
+        };
+    }
+
+    pub fn is_list(&self) -> bool {
+        return matches!(self, Type::List(_));
+    }
+
+    pub fn numeric_result(&self, other: &Type) -> Type {
+        if *self == Type::Float || *other == Type::Float {
+            return Type::Float;
+        }
+        return Type::Integer;
+    }
+}
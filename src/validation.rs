@@ -0,0 +1,764 @@
+use crate::alternative::*;
+use crate::choice::*;
+use crate::cost::*;
+use crate::declaration::*;
+use crate::entry::*;
+use crate::error::*;
+use crate::option::*;
+use crate::requirement::*;
+use crate::symbol_table::*;
+use crate::token::Span;
+use crate::types::Type;
+use crate::value::*;
+use std::fmt::Display;
+
+// A rule of the codex that a roster breaks. The span points into the
+// roster, requirement is the span of the violated `require` declaration,
+// if the violation comes from one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub message: String,
+    pub span: Span,
+    pub requirement: Option<Span>,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "{}", self.message);
+    }
+}
+
+impl Violation {
+    pub fn new(message: String, span: Span) -> Violation {
+        return Violation {
+            message,
+            span,
+            requirement: None,
+        };
+    }
+}
+
+// The document the spans of an error point into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Origin {
+    Codex,
+    Roster,
+}
+
+// An error met while validating a roster. Most come from codex
+// declarations that cannot be evaluated, such as a cost that is not a
+// number, others from roster selections, such as quantities whose points
+// overflow.
+#[derive(Debug, Clone)]
+pub struct ValidationError {
+    pub origin: Origin,
+    pub error: ParserError,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return write!(f, "{}", self.error);
+    }
+}
+
+impl From<ParserError> for ValidationError {
+    fn from(error: ParserError) -> ValidationError {
+        return ValidationError {
+            origin: Origin::Codex,
+            error,
+        };
+    }
+}
+
+fn in_roster(error: ParserError) -> ValidationError {
+    return ValidationError {
+        origin: Origin::Roster,
+        error,
+    };
+}
+
+// Whether a roster value can replace the value of a codex field. Records
+// are compared by their fields when they are used, not by their name.
+fn accepts(expected: &Type, found: &Type) -> bool {
+    return match (expected, found) {
+        (Type::Record(_), Type::Record(_)) => true,
+        _ => expected.unify(found).is_some(),
+    };
+}
+
+fn describe_type(t: &Type) -> String {
+    return match t {
+        Type::List(None) => "list".to_owned(),
+        other => other.to_string(),
+    };
+}
+
+fn quantity(choice: &ChoiseDeclaration) -> i64 {
+    return choice.quantity().unwrap_or(1);
+}
+
+fn describe_bounds(min: i64, max: i64) -> String {
+    if min == max {
+        return min.to_string();
+    }
+    return format!("{}-{}", min, max);
+}
+
+struct Validator<'a> {
+    codex: &'a [Declaration],
+    violations: Vec<Violation>,
+}
+
+impl<'a> Validator<'a> {
+    fn violation(&mut self, message: String, span: Span) {
+        self.violations.push(Violation::new(message, span));
+    }
+
+    // A requirement that cannot be evaluated for the selections of a
+    // roster, such as one dividing by a count that is zero, is broken by
+    // that roster and does not stop the validation of the others.
+    fn check_requirement(
+        &mut self,
+        requirement: &RequirementDeclaration,
+        span: Span,
+        scope: &SymbolTable,
+    ) -> Result<(), ValidationError> {
+        let message = match requirement.condition().evaluate(scope) {
+            Ok(Value::Bool(false)) => requirement.message().clone(),
+            Ok(_) => return Ok(()),
+            Err(error) => format!("cannot check \"{}\": {}", requirement.message(), error),
+        };
+        self.violations.push(Violation {
+            message,
+            span,
+            requirement: Some(requirement.span()),
+        });
+        return Ok(());
+    }
+
+    // Every option of the codex, at any depth, which roster values such as
+    // `heavy weapons = [heavy bolter]` refer to by name.
+    fn pickable(&self) -> Vec<&'a OptionDeclaration> {
+        fn collect<'c>(option: &'c OptionDeclaration, options: &mut Vec<&'c OptionDeclaration>) {
+            options.push(option);
+            for field in option.get_fields() {
+                match field {
+                    OptionField::SubOption(sub) => collect(sub, options),
+                    OptionField::Alt(alternative) => {
+                        for nested in alternative.get_fields() {
+                            collect(nested, options);
+                        }
+                    }
+                    OptionField::Const(_) | OptionField::Require(_) => {}
+                }
+            }
+        }
+        let mut options = Vec::new();
+        for decl in self.codex {
+            match decl {
+                Declaration::Opt(option) => collect(option, &mut options),
+                Declaration::Alt(alternative) => {
+                    for option in alternative.get_fields() {
+                        collect(option, &mut options);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return options;
+    }
+
+    // The names a roster value may pick, unless a constant or a field of
+    // the same name is in scope.
+    fn picks(&self, table: &SymbolTable) -> Vec<(&'a String, Value)> {
+        let mut picks = Vec::new();
+        for option in self.pickable() {
+            let hidden = matches!(
+                table.get(option.name()),
+                Some(Symbol::Const(_)) | Some(Symbol::Field(_)) | Some(Symbol::Entry(_))
+            );
+            if !hidden {
+                picks.push((option.name(), Value::Str(option.name().clone())));
+            }
+        }
+        return picks;
+    }
+
+    fn find_pick(&self, name: &str) -> Option<&'a OptionDeclaration> {
+        let name = normalize_name(name);
+        return self
+            .pickable()
+            .into_iter()
+            .find(|x| normalize_name(x.name()) == name);
+    }
+
+    fn find_alternative_option(&self, name: &str) -> Option<&'a OptionDeclaration> {
+        let name = normalize_name(name);
+        for decl in self.codex {
+            if let Declaration::Alt(alternative) = decl {
+                let option = alternative
+                    .get_fields()
+                    .iter()
+                    .find(|x| normalize_name(x.name()) == name);
+                if option.is_some() {
+                    return option;
+                }
+            }
+        }
+        return None;
+    }
+
+    fn validate_choice(
+        &mut self,
+        choice: &ChoiseDeclaration,
+        table: &SymbolTable,
+    ) -> Result<Points, ValidationError> {
+        return match table.get(choice.symbol()) {
+            Some(Symbol::Entry(entry)) => self.validate_entry(entry, choice, table),
+            Some(Symbol::Opt(option)) => self.validate_option(option, choice, table),
+            Some(Symbol::Alt(alternative)) => self.validate_alternative(alternative, choice, table),
+            _ => match self.find_alternative_option(choice.symbol()) {
+                Some(option) => self.validate_option(option, choice, table),
+                None => {
+                    self.violation(
+                        format!("unknown selection `{}`", choice.symbol()),
+                        choice.span(),
+                    );
+                    Ok(points_of(choice))
+                }
+            },
+        };
+    }
+
+    fn validate_entry(
+        &mut self,
+        entry: &EntryDeclaration,
+        choice: &ChoiseDeclaration,
+        table: &SymbolTable,
+    ) -> Result<Points, ValidationError> {
+        let mut scope = entry.make_scope(table)?;
+        let mut points = points_of(choice);
+
+        let mut selected: Vec<Vec<&ChoiseDeclaration>> = vec![Vec::new(); entry.get_fields().len()];
+        for child in choice.get_fields() {
+            let name = normalize_name(child.symbol());
+            match entry
+                .get_fields()
+                .iter()
+                .position(|x| normalize_name(x.name()) == name)
+            {
+                Some(index) => selected[index].push(child),
+                None => self.violation(
+                    format!("`{}` is not a field of `{}`", child.symbol(), entry.name()),
+                    child.span(),
+                ),
+            }
+        }
+
+        let picks = self.picks(table);
+        let mut values = Vec::new();
+        let mut models = 0;
+        for (field, children) in entry.get_fields().iter().zip(&selected) {
+            let value = self.field_value(field, children, &scope, &picks)?;
+            let count = match (&value, children.is_empty()) {
+                (Value::List(elements), _) => elements.len() as i64,
+                (_, true) => 0,
+                (_, false) => children.iter().map(|x| quantity(x)).sum(),
+            };
+            let value = match value {
+                Value::Selection(_) => {
+                    models += count;
+                    Value::Selection(count)
+                }
+                other => other,
+            };
+
+            if count < field.min() || count > field.max() {
+                let span = children.last().map_or(choice.span(), |x| x.span());
+                self.violation(
+                    format!(
+                        "expected {} `{}` in `{}`, found {}",
+                        describe_bounds(field.min(), field.max()),
+                        field.name(),
+                        entry.name(),
+                        count
+                    ),
+                    span,
+                );
+            }
+            for child in children {
+                for nested in child.get_fields() {
+                    self.violation(
+                        format!(
+                            "`{}` cannot be selected in `{}`",
+                            nested.symbol(),
+                            field.name()
+                        ),
+                        nested.span(),
+                    );
+                }
+            }
+            values.push((field, children, count, value));
+        }
+        for (field, _, _, value) in &values {
+            scope.bind(field.name(), value.clone());
+        }
+
+        for requirement in entry.get_requirements() {
+            self.check_requirement(requirement, choice.span(), &scope)?;
+        }
+
+        points
+            .set_cost(declared_cost(entry.get_constants().iter(), models, &scope)?)
+            .map_err(in_roster)?;
+        for (field, children, count, value) in values {
+            if count == 0 {
+                continue;
+            }
+            let span = children.last().map_or(choice.span(), |x| x.span());
+            let mut field_points = Points::new(field.name().clone(), Some(count), span);
+            field_points
+                .set_cost(declared_cost(field.get_fields().iter(), count, &scope)?)
+                .map_err(in_roster)?;
+            if let Value::List(elements) = value {
+                field_points.quantity = None;
+                for element in elements {
+                    field_points
+                        .add_child(self.pick_points(&element, span, table)?)
+                        .map_err(in_roster)?;
+                }
+            }
+            points.add_child(field_points).map_err(in_roster)?;
+        }
+        // `2 tactical squad` is two identical units.
+        if let Some(quantity) = choice.quantity() {
+            let total = points.total.mult(&Value::Integer(quantity));
+            points.total = total
+                .ok_or_else(|| in_roster(ParserError::new_arithmetic_overflow(choice.span())))?;
+        }
+        return Ok(points);
+    }
+
+    // A pick of a list value, such as `heavy bolter` in
+    // `heavy weapons = [heavy bolter]`, costs as much as the option it
+    // names. Other values cost nothing.
+    fn pick_points(
+        &mut self,
+        element: &Value,
+        span: Span,
+        table: &SymbolTable,
+    ) -> Result<Points, ValidationError> {
+        if let Value::Str(name) = element {
+            if let Some(option) = self.find_pick(name) {
+                let pick = ChoiseDeclaration::new(name.clone(), None, span);
+                return self.validate_option(option, &pick, table);
+            }
+        }
+        return Ok(Points::new(element.to_string(), None, span));
+    }
+
+    // The value of a field is the value written in the roster, or the
+    // initializer of the codex when the roster does not override it.
+    // Fields without any value are selections. Roster values that cannot
+    // be evaluated, or whose type differs from the one of the codex, are
+    // violations and the codex value is used instead.
+    fn field_value(
+        &mut self,
+        field: &EntryField,
+        children: &[&ChoiseDeclaration],
+        scope: &SymbolTable,
+        picks: &[(&String, Value)],
+    ) -> Result<Value, ValidationError> {
+        if let Some(expression) = children.iter().rev().find_map(|x| x.get_value()) {
+            let mut roster_scope = scope.make_child();
+            for (name, value) in picks {
+                roster_scope.bind(name, value.clone());
+            }
+            match expression.evaluate(&roster_scope) {
+                Ok(value) => {
+                    let expected = match field.get_initializer() {
+                        Some(initializer) => initializer.type_check(scope)?,
+                        None => Type::Selection,
+                    };
+                    if accepts(&expected, &value.type_of()) {
+                        return Ok(value);
+                    }
+                    self.violation(
+                        format!(
+                            "expected {} for `{}`, found {}",
+                            describe_type(&expected),
+                            field.name(),
+                            describe_type(&value.type_of())
+                        ),
+                        expression.span(),
+                    );
+                }
+                Err(error) => match error.get_unresolved_name() {
+                    Some(unresolved) => self.violation(
+                        format!(
+                            "`{}` is not an option that can be picked for `{}`",
+                            unresolved.name,
+                            field.name()
+                        ),
+                        unresolved.span,
+                    ),
+                    None => self.violation(error.to_string(), expression.span()),
+                },
+            };
+        }
+        return match field.get_initializer() {
+            Some(initializer) => Ok(initializer.evaluate(scope)?),
+            None => Ok(Value::Selection(0)),
+        };
+    }
+
+    fn validate_alternative(
+        &mut self,
+        alternative: &AlternativeDeclaration,
+        choice: &ChoiseDeclaration,
+        table: &SymbolTable,
+    ) -> Result<Points, ValidationError> {
+        let mut points = points_of(choice);
+        let mut picked: Option<&ChoiseDeclaration> = None;
+        for child in choice.get_fields() {
+            let name = normalize_name(child.symbol());
+            let option = alternative
+                .get_fields()
+                .iter()
+                .find(|x| normalize_name(x.name()) == name);
+            match option {
+                Some(option) => {
+                    self.check_exclusive(alternative, &mut picked, child);
+                    points
+                        .add_child(self.validate_option(option, child, table)?)
+                        .map_err(in_roster)?;
+                }
+                None => self.violation(
+                    format!(
+                        "`{}` is not an option of `{}`",
+                        child.symbol(),
+                        alternative.name()
+                    ),
+                    child.span(),
+                ),
+            }
+        }
+        return Ok(points);
+    }
+
+    fn check_exclusive<'c>(
+        &mut self,
+        alternative: &AlternativeDeclaration,
+        picked: &mut Option<&'c ChoiseDeclaration>,
+        child: &'c ChoiseDeclaration,
+    ) {
+        match picked {
+            Some(previous) => self.violation(
+                format!(
+                    "only one option of `{}` can be selected, found `{}` and `{}`",
+                    alternative.name(),
+                    previous.symbol(),
+                    child.symbol()
+                ),
+                child.span(),
+            ),
+            None => *picked = Some(child),
+        }
+    }
+
+    fn validate_option(
+        &mut self,
+        option: &OptionDeclaration,
+        choice: &ChoiseDeclaration,
+        table: &SymbolTable,
+    ) -> Result<Points, ValidationError> {
+        let mut scope = table.make_child();
+        let mut points = points_of(choice);
+        let mut counts = Vec::new();
+        for field in option.get_fields() {
+            match field {
+                OptionField::SubOption(decl) => {
+                    scope.insert_symbol(decl.name(), Symbol::Opt(decl))?;
+                    counts.push((decl.name(), 0));
+                }
+                OptionField::Const(decl) => {
+                    scope.insert_symbol(decl.name(), Symbol::Const(decl))?
+                }
+                OptionField::Alt(decl) => {
+                    scope.insert_symbol(decl.name(), Symbol::Alt(decl))?;
+                    counts.push((decl.name(), 0));
+                }
+                OptionField::Require(_) => {}
+            };
+        }
+
+        let mut picked: Vec<Option<&ChoiseDeclaration>> = vec![None; option.get_fields().len()];
+        for child in choice.get_fields() {
+            let name = normalize_name(child.symbol());
+            let mut found = false;
+            for (index, field) in option.get_fields().iter().enumerate() {
+                match field {
+                    OptionField::SubOption(decl) if normalize_name(decl.name()) == name => {
+                        points
+                            .add_child(self.validate_option(decl, child, &scope)?)
+                            .map_err(in_roster)?;
+                        add_count(&mut counts, decl.name(), quantity(child));
+                        found = true;
+                    }
+                    OptionField::Alt(alternative) => {
+                        let option = alternative
+                            .get_fields()
+                            .iter()
+                            .find(|x| normalize_name(x.name()) == name);
+                        if let Some(option) = option {
+                            self.check_exclusive(alternative, &mut picked[index], child);
+                            points
+                                .add_child(self.validate_option(option, child, &scope)?)
+                                .map_err(in_roster)?;
+                            add_count(&mut counts, alternative.name(), quantity(child));
+                            found = true;
+                        }
+                    }
+                    _ => {}
+                }
+                if found {
+                    break;
+                }
+            }
+            if !found {
+                self.violation(
+                    format!(
+                        "`{}` is not an option of `{}`",
+                        child.symbol(),
+                        option.name()
+                    ),
+                    child.span(),
+                );
+            }
+        }
+        for (name, count) in counts {
+            scope.bind(name, Value::Selection(count));
+        }
+
+        for field in option.get_fields() {
+            if let OptionField::Require(requirement) = field {
+                self.check_requirement(requirement, choice.span(), &scope)?;
+            }
+        }
+
+        let constants = option.get_fields().iter().filter_map(|x| match x {
+            OptionField::Const(constant) => Some(constant),
+            _ => None,
+        });
+        points
+            .set_cost(declared_cost(constants, quantity(choice), &scope)?)
+            .map_err(in_roster)?;
+        return Ok(points);
+    }
+}
+
+fn points_of(choice: &ChoiseDeclaration) -> Points {
+    return Points::new(choice.symbol().clone(), choice.quantity(), choice.span());
+}
+
+fn add_count(counts: &mut [(&String, i64)], name: &str, quantity: i64) {
+    if let Some(entry) = counts.iter_mut().find(|x| x.0 == name) {
+        entry.1 += quantity;
+    }
+}
+
+// Checks the selections of a roster against the declarations of a codex.
+// Broken rules are returned as violations, errors are only returned when
+// the codex itself is invalid or a requirement cannot be evaluated.
+pub fn validate(
+    codex: &[Declaration],
+    roster: &[Declaration],
+) -> Result<Vec<Violation>, ValidationError> {
+    return Ok(walk(codex, roster)?.0);
+}
+
+// The points of every selection of a roster, the root of the returned tree
+// holds the total of the roster. Selections that break the rules of the
+// codex are still counted.
+pub fn points(codex: &[Declaration], roster: &[Declaration]) -> Result<Points, ValidationError> {
+    return Ok(walk(codex, roster)?.1);
+}
+
+fn walk(
+    codex: &[Declaration],
+    roster: &[Declaration],
+) -> Result<(Vec<Violation>, Points), ValidationError> {
+    let mut table = SymbolTable::new();
+    for decl in codex {
+        table.insert(decl)?;
+    }
+
+    let mut validator = Validator {
+        codex,
+        violations: Vec::new(),
+    };
+    let mut span: Option<Span> = None;
+    let mut counts: Vec<(String, i64)> = Vec::new();
+    let mut total = Points::new("total".to_owned(), None, Span::new());
+    for decl in roster {
+        if let Declaration::Choice(choice) = decl {
+            total
+                .add_child(validator.validate_choice(choice, &table)?)
+                .map_err(in_roster)?;
+            span = Some(span.map_or(choice.span(), |x| x.merge(&choice.span())));
+            let name = normalize_name(choice.symbol());
+            match counts.iter_mut().find(|x| x.0 == name) {
+                Some(entry) => entry.1 += quantity(choice),
+                None => counts.push((name, quantity(choice))),
+            }
+        }
+    }
+
+    // Top level requirements see how many times each entry and option was
+    // selected in the whole roster.
+    let mut scope = table.make_child();
+    for decl in codex {
+        if matches!(decl, Declaration::Entry(_) | Declaration::Opt(_)) {
+            let name = normalize_name(&decl.name());
+            let count = counts.iter().find(|x| x.0 == name).map_or(0, |x| x.1);
+            scope.bind(&name, Value::Selection(count));
+        }
+    }
+    for decl in codex {
+        if let Declaration::Require(requirement) = decl {
+            validator.check_requirement(requirement, span.unwrap_or_default(), &scope)?;
+        }
+    }
+    total.span = span.unwrap_or_default();
+    return Ok((validator.violations, total));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::validation::*;
+
+    const CODEX: &str = "const marine profile\n WS = 4\n\nconst scout profile\n WS = 3\n\nalt heavy weapon\n heavy bolter\n lascannon\n\nentry tactical squad\n 5-10 tactical marines\n  profile = marine profile\n 0-1 sergent\n 0-1 heavy weapons = []\n require\n  count(tactical marines) + count(sergent) <= 10\n  \"squad cannot have more than 10 models\"\n require\n  tactical marines.profile.WS >= 4\n  \"marines must be skilled\"\n\nopt captain\n alt weapon\n  power sword\n  power fist\n jump pack\n require\n  count(weapon) == 1\n  \"captain needs a weapon\"\n\nrequire\n count(captain) <= 1\n \"at most one captain\"\n";
+
+    fn violations(roster: &str) -> Vec<Violation> {
+        let codex = Parser::parse(CODEX).unwrap();
+        let roster = Parser::parse(roster).unwrap();
+        return roster.validate_against(&codex).unwrap();
+    }
+
+    #[test]
+    fn valid_roster() {
+        let roster = "1 tactical squad\n 9 tactical marines\n 1 sergent\n heavy weapons = [\n  heavy bolter\n ]\n\ncaptain\n power fist\n jump pack\n";
+        assert_eq!(violations(roster), vec![]);
+
+        let combined = Parser::parse(&format!("{}\n{}", CODEX, roster)).unwrap();
+        assert_eq!(combined.validate().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn cardinality_bounds() {
+        let result = violations(
+            "tactical squad\n 3 tactical marines\n heavy weapons = [lascannon, heavy bolter]\n",
+        );
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            result[0].message,
+            "expected 5-10 `tactical marines` in `tactical squad`, found 3"
+        );
+        assert_eq!(result[0].span.lo.row, 1);
+        assert_eq!(result[1].span.lo.row, 2);
+
+        let result = violations("tactical squad\n 10 tactical marines\n 1 sergent\n");
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].message, "squad cannot have more than 10 models");
+        assert_eq!(result[0].span.lo.row, 0);
+        assert_eq!(result[0].requirement.unwrap().lo.row, 15);
+    }
+
+    #[test]
+    fn alternative_exclusivity() {
+        let result = violations("captain\n power sword\n power fist\n");
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            result[0].message,
+            "only one option of `weapon` can be selected, found `power sword` and `power fist`"
+        );
+        assert_eq!(result[0].span.lo.row, 2);
+        assert_eq!(result[1].message, "captain needs a weapon");
+
+        let result = violations("captain\n\ncaptain\n power sword\n");
+        let messages: Vec<&str> = result.iter().map(|x| x.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["captain needs a weapon", "at most one captain"]
+        );
+    }
+
+    #[test]
+    fn unknown_selections() {
+        let result = violations("tactical squad\n 5 tactical marines\n 1 librarian\n heavy weapons = [missile launcher]\n\ndreadnought\n");
+        let messages: Vec<&str> = result.iter().map(|x| x.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`librarian` is not a field of `tactical squad`",
+                "`missile launcher` is not an option that can be picked for `heavy weapons`",
+                "unknown selection `dreadnought`"
+            ]
+        );
+    }
+
+    #[test]
+    fn roster_values_match_field_types() {
+        let result =
+            violations("tactical squad\n 5 tactical marines = \"x\"\n heavy weapons = 3\n");
+        let messages: Vec<&str> = result.iter().map(|x| x.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "expected selection for `tactical marines`, found string",
+                "expected list for `heavy weapons`, found integer"
+            ]
+        );
+        assert_eq!(result[1].span.lo.row, 2);
+        assert_eq!(result[1].span.lo.column, 17);
+    }
+
+    #[test]
+    fn picks_from_nested_options() {
+        let codex = "opt heavy bolter\n const cost = 10\n\nopt wargear\n plasma gun\n  const cost = 15\n alt grenades\n  krak grenades\n\nentry squad\n 1-5 marines\n 0-3 weapons = []\n";
+        let codex = Parser::parse(codex).unwrap();
+        let roster = Parser::parse(
+            "squad\n 5 marines\n weapons = [heavy bolter, plasma gun, krak grenades]\n",
+        )
+        .unwrap();
+        assert_eq!(roster.validate_against(&codex).unwrap(), vec![]);
+        let total = roster.points_against(&codex).unwrap();
+        assert_eq!(total.total, Value::Integer(25));
+
+        let roster = Parser::parse("squad\n 5 marines\n weapons = [bolter]\n").unwrap();
+        let result = roster.validate_against(&codex).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].message,
+            "`bolter` is not an option that can be picked for `weapons`"
+        );
+        assert_eq!((result[0].span.lo.row, result[0].span.lo.column), (2, 12));
+    }
+
+    #[test]
+    fn roster_points() {
+        let codex = "alt heavy weapon\n heavy bolter\n  const cost = 10\n lascannon\n  const cost = 20\n\nentry tactical squad\n const cost = 20\n const cost per model = 14\n 5-10 tactical marines\n 0-1 sergent\n  cost = 5\n 0-1 heavy weapons = []\n\nopt captain\n const cost = 80\n alt weapon\n  power sword\n   const cost = 5\n  power fist\n   const cost = 10\n";
+        let roster = "2 tactical squad\n 5 tactical marines\n 1 sergent\n heavy weapons = [lascannon]\n\ncaptain\n power fist\n";
+        let codex = Parser::parse(codex).unwrap();
+        let roster = Parser::parse(roster).unwrap();
+        assert_eq!(roster.validate_against(&codex).unwrap(), vec![]);
+
+        let total = roster.points_against(&codex).unwrap();
+        assert_eq!(total.total, Value::Integer(2 * (20 + 6 * 14 + 5 + 20) + 90));
+        assert_eq!(
+            total.to_string(),
+            "total: 348\n 2 tactical squad: 258\n  5 tactical marines: 0\n  1 sergent: 5\n  heavy weapons: 20\n   lascannon: 20\n captain: 90\n  power fist: 10\n"
+        );
+    }
+}
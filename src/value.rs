@@ -0,0 +1,205 @@
+use crate::serialization::escape;
+use crate::types::Type;
+use std::fmt::Display;
+
+// Arithmetic between two integers stays integral and fails on overflow.
+// As soon as one operand is a float the other one is promoted and the
+// operation is carried out in floating point. Division by zero fails for
+// both integers and floats, integer division truncates toward zero.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    Record(Vec<(String, Value)>),
+    List(Vec<Value>),
+    // A codex declaration selected this many times in a roster.
+    Selection(i64),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        return match self {
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(v) => write!(f, "{:?}", v),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Str(s) => write!(f, "\"{}\"", escape(s)),
+            Value::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} = {}", name, value)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Selection(count) => write!(f, "selection of {}", count),
+            Value::List(elements) => {
+                write!(f, "[")?;
+                for (i, value) in elements.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+        };
+    }
+}
+
+impl Value {
+    // Records are typed by their fields only, so their type carries no name.
+    pub fn type_of(&self) -> Type {
+        return match self {
+            Value::Integer(_) => Type::Integer,
+            Value::Float(_) => Type::Float,
+            Value::Bool(_) => Type::Bool,
+            Value::Str(_) => Type::Str,
+            Value::Record(_) => Type::Record("".to_owned()),
+            Value::Selection(_) => Type::Selection,
+            Value::List(elements) => {
+                let mut element: Option<Type> = None;
+                for value in elements {
+                    let value = value.type_of();
+                    element = match element {
+                        None => Some(value),
+                        Some(previous) => Some(previous.unify(&value).unwrap_or(previous)),
+                    };
+                }
+                Type::List(element.map(Box::new))
+            }
+        };
+    }
+
+    pub fn get_field(&self, name: &str) -> Option<&Value> {
+        return match self {
+            Value::Record(fields) => fields.iter().find(|x| x.0 == name).map(|x| &x.1),
+            _ => None,
+        };
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        return matches!(self, Value::Integer(_) | Value::Float(_));
+    }
+
+    pub fn contains(&self, element: &Value) -> Option<bool> {
+        return match self {
+            Value::List(elements) => {
+                for value in elements {
+                    if value.equals(element)? {
+                        return Some(true);
+                    }
+                }
+                Some(false)
+            }
+            _ => None,
+        };
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        return match self {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Float(v) => Some(*v),
+            _ => None,
+        };
+    }
+
+    fn arithmetic(
+        &self,
+        other: &Value,
+        int_op: fn(i64, i64) -> Option<i64>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Option<Value> {
+        if let (Value::Integer(lhs), Value::Integer(rhs)) = (self, other) {
+            return int_op(*lhs, *rhs).map(Value::Integer);
+        }
+        let result = float_op(self.as_float()?, other.as_float()?);
+        if !result.is_finite() {
+            return None;
+        }
+        return Some(Value::Float(result));
+    }
+
+    pub fn add(&self, other: &Value) -> Option<Value> {
+        return self.arithmetic(other, i64::checked_add, |a, b| a + b);
+    }
+
+    pub fn sub(&self, other: &Value) -> Option<Value> {
+        return self.arithmetic(other, i64::checked_sub, |a, b| a - b);
+    }
+
+    pub fn mult(&self, other: &Value) -> Option<Value> {
+        return self.arithmetic(other, i64::checked_mul, |a, b| a * b);
+    }
+
+    pub fn div(&self, other: &Value) -> Option<Value> {
+        return self.arithmetic(other, i64::checked_div, |a, b| a / b);
+    }
+
+    pub fn neg(&self) -> Option<Value> {
+        return match self {
+            Value::Integer(i) => i.checked_neg().map(Value::Integer),
+            Value::Float(v) => Some(Value::Float(-v)),
+            _ => None,
+        };
+    }
+
+    pub fn compare(&self, other: &Value) -> Option<std::cmp::Ordering> {
+        if let (Value::Integer(lhs), Value::Integer(rhs)) = (self, other) {
+            return Some(lhs.cmp(rhs));
+        }
+        return self.as_float()?.partial_cmp(&other.as_float()?);
+    }
+
+    pub fn equals(&self, other: &Value) -> Option<bool> {
+        return match (self, other) {
+            (Value::Bool(lhs), Value::Bool(rhs)) => Some(lhs == rhs),
+            (Value::Str(lhs), Value::Str(rhs)) => Some(lhs == rhs),
+            (Value::Record(lhs), Value::Record(rhs)) => Some(lhs == rhs),
+            (Value::List(lhs), Value::List(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Some(false);
+                }
+                for (lhs, rhs) in lhs.iter().zip(rhs) {
+                    if !lhs.equals(rhs)? {
+                        return Some(false);
+                    }
+                }
+                Some(true)
+            }
+            _ => Some(self.compare(other)? == std::cmp::Ordering::Equal),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::*;
+
+    #[test]
+    fn integer_arithmetic() {
+        let seven = Value::Integer(7);
+        let two = Value::Integer(2);
+        assert_eq!(seven.add(&two), Some(Value::Integer(9)));
+        assert_eq!(seven.div(&two), Some(Value::Integer(3)));
+        assert_eq!(seven.div(&Value::Integer(0)), None);
+        assert_eq!(Value::Integer(i64::MAX).add(&two), None);
+    }
+
+    #[test]
+    fn mixed_arithmetic() {
+        let seven = Value::Integer(7);
+        let half = Value::Float(0.5);
+        assert_eq!(seven.mult(&half), Some(Value::Float(3.5)));
+        assert_eq!(half.sub(&seven), Some(Value::Float(-6.5)));
+        assert_eq!(seven.div(&Value::Float(0.0)), None);
+        assert_eq!(
+            Value::Integer(1).compare(&Value::Float(1.5)),
+            Some(std::cmp::Ordering::Less)
+        );
+        assert_eq!(Value::Integer(2).equals(&Value::Float(2.0)), Some(true));
+    }
+}